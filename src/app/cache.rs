@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::app::calculator::{self, Req};
+use crate::app::i18n::tr;
+use crate::app::App;
+
+// 容量过小时每次淘汰都接近清空，缓存失去意义
+pub const MIN_CAPACITY: usize = 1_000;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct CacheSettings {
+    pub capacity: usize,
+    // 退出时是否把缓存写入磁盘
+    pub persist: bool,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 100_000,
+            persist: false,
+        }
+    }
+}
+
+// 写入磁盘的缓存，计算规则版本不一致时整体作废
+#[derive(Serialize, Deserialize)]
+pub struct PersistedCache {
+    version: u32,
+    entries: Vec<(Req, (Decimal, Decimal))>,
+}
+
+struct Entry {
+    value: (Decimal, Decimal),
+    used: u64,
+}
+
+// 按最近使用淘汰的计算结果缓存
+pub struct Cache {
+    map: HashMap<Req, Entry>,
+    capacity: usize,
+    tick: u64,
+    // 上次写入磁盘后是否有变化
+    changed: bool,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Cache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            map: HashMap::new(),
+            capacity: capacity.max(MIN_CAPACITY),
+            tick: 0,
+            changed: false,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(MIN_CAPACITY);
+        if self.map.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    pub fn get(&mut self, req: &Req) -> Option<(Decimal, Decimal)> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.map.get_mut(req).map(|entry| {
+            entry.used = tick;
+            entry.value
+        })
+    }
+
+    // 提交计算前查找，计入命中统计
+    pub fn lookup(&mut self, req: &Req) -> Option<(Decimal, Decimal)> {
+        let res = self.get(req);
+        if res.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        res
+    }
+
+    pub fn insert(&mut self, req: Req, value: (Decimal, Decimal)) {
+        self.tick += 1;
+        self.changed = true;
+        self.map.insert(
+            req,
+            Entry {
+                value,
+                used: self.tick,
+            },
+        );
+        if self.map.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.changed = true;
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn mark_changed(&mut self) {
+        self.changed = true;
+    }
+
+    // 返回是否需要写入磁盘，并清除标记
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn to_persisted(&self) -> PersistedCache {
+        PersistedCache {
+            version: calculator::VERSION,
+            entries: self.map.iter().map(|(k, e)| (*k, e.value)).collect(),
+        }
+    }
+
+    pub fn load(&mut self, persisted: PersistedCache) {
+        if persisted.version != calculator::VERSION {
+            return;
+        }
+        persisted
+            .entries
+            .into_iter()
+            .for_each(|(req, value)| self.insert(req, value));
+        // 刚从磁盘读出，无需再写回
+        self.changed = false;
+    }
+
+    // 一次淘汰到容量的九成，避免每次插入都排序
+    fn evict(&mut self) {
+        self.changed = true;
+        let keep = self.capacity - self.capacity / 10;
+        let mut used: Vec<u64> = self.map.values().map(|e| e.used).collect();
+        let nth = used.len() - keep;
+        let (_, threshold, _) = used.select_nth_unstable(nth);
+        let threshold = *threshold;
+        self.map.retain(|_, e| e.used >= threshold);
+    }
+}
+
+impl App {
+    pub(super) fn show_cache(&mut self, ctx: &egui::Context) {
+        let mut open = self.cache_open;
+        egui::Window::new(tr!("缓存"))
+            .id(egui::Id::new("cache"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let cache = &mut self.cache;
+                egui::Grid::new("缓存统计").show(ui, |ui| {
+                    ui.label(tr!("条目"));
+                    ui.label(format!("{}/{}", cache.len(), cache.capacity()));
+                    ui.end_row();
+
+                    ui.label(tr!("命中"));
+                    ui.label(cache.hits.to_string());
+                    ui.end_row();
+
+                    ui.label(tr!("未命中"));
+                    ui.label(cache.misses.to_string());
+                    ui.end_row();
+
+                    ui.label(tr!("命中率"));
+                    let total = cache.hits + cache.misses;
+                    if total > 0 {
+                        ui.label(format!("{:.1}%", cache.hits as f64 * 100.0 / total as f64));
+                    } else {
+                        ui.label("-");
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("容量"));
+                    let mut capacity = self.cache_settings.capacity;
+                    let drag =
+                        egui::DragValue::new(&mut capacity).clamp_range(MIN_CAPACITY..=10_000_000);
+                    if ui.add(drag).changed() {
+                        self.cache_settings.capacity = capacity;
+                        cache.set_capacity(capacity);
+                    }
+                    ui.end_row();
+                });
+
+                if ui
+                    .checkbox(&mut self.cache_settings.persist, tr!("退出时保存缓存"))
+                    .changed()
+                {
+                    cache.mark_changed();
+                }
+                if ui.button(tr!("清空")).clicked() {
+                    cache.clear();
+                }
+            });
+        self.cache_open = open;
+    }
+}
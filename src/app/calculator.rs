@@ -1,35 +1,83 @@
 use std::cmp::min;
 
 use anyhow::{anyhow, bail, Result};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 use rust_decimal::RoundingStrategy::{MidpointAwayFromZero, ToZero};
+use serde::{Deserialize, Serialize};
 use time::{util, Date, Duration, Month};
 
-use crate::app::config::{Order, Product, RenewType, TermType};
+use crate::app::config::{
+    BeanRule, Cd, FixedTerm, Inflation, Notice, Order, OrderMode, Payout, Product, ProductKind,
+    Recurring, RenewType, TermType, Withdrawal,
+};
+use crate::app::i18n::tr;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+// 计算规则版本，规则变化时加一，使持久化的缓存失效
+pub const VERSION: u32 = 10;
+
+// 提前支取按活期利率计息(%)
+pub const DEMAND_RATE: Decimal = Decimal::from_parts(35, 0, 0, false, 2);
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Req {
     pub principal: Decimal,
     pub save_date: u32,
     pub draw_date: u32,
-    pub term: u8,
+    // 零存整取时按期存入，忽略本金与产品存期等，见 Req::new
+    pub recurring: Option<Recurring>,
+    pub withdrawal: Option<Withdrawal>,
+    pub kind: ProductKind,
+    pub fixed: FixedTerm,
+    pub notice: Notice,
+    pub term: u16,
     pub term_type: TermType,
     pub int_rate: Decimal,
     pub bean_rate: Decimal,
+    pub bean_rule: BeanRule,
     pub renew_type: RenewType,
+    pub payout: Payout,
 }
 
 impl Req {
     pub fn new(order: &Order, product: &Product) -> Self {
-        Self {
+        let req = Self {
             principal: order.principal,
             save_date: order.save_date,
             draw_date: order.draw_date,
+            recurring: match order.mode {
+                OrderMode::LumpSum => None,
+                OrderMode::Recurring => Some(order.recurring),
+            },
+            withdrawal: match order.mode {
+                OrderMode::LumpSum => order.withdrawal,
+                OrderMode::Recurring => None,
+            },
+            kind: product.kind,
+            fixed: product.fixed,
+            notice: product.notice,
             term: product.term,
             term_type: product.term_type,
             int_rate: product.int_rate,
             bean_rate: product.bean_rate,
+            bean_rule: product.bean_rule,
             renew_type: product.renew_type,
+            payout: product.payout,
+        };
+        match order.mode {
+            OrderMode::LumpSum => req,
+            // 零存整取只用到利率与邦豆规则，其余字段归一，相同的计算共用缓存
+            OrderMode::Recurring => Self {
+                principal: Decimal::ZERO,
+                kind: ProductKind::default(),
+                fixed: FixedTerm::default(),
+                notice: Notice::default(),
+                term: 0,
+                term_type: TermType::default(),
+                renew_type: RenewType::default(),
+                payout: Payout::default(),
+                ..req
+            },
         }
     }
 }
@@ -37,10 +85,21 @@ impl Req {
 pub fn u32_to_date(date: u32) -> Result<Date> {
     Date::from_calendar_date(
         (date / 10000) as i32,
-        Month::try_from((date / 100 % 100) as u8).map_err(|e| anyhow!("月份有误！{e}"))?,
+        Month::try_from((date / 100 % 100) as u8).map_err(|e| anyhow!(tr!("月份有误！{}", e)))?,
         (date % 100) as u8,
     )
-    .map_err(|e| anyhow!("日期有误!{e}"))
+    .map_err(|e| anyhow!(tr!("日期有误!{}", e)))
+}
+
+pub fn date_to_u32(date: Date) -> u32 {
+    date.year() as u32 * 10000 + date.month() as u32 * 100 + date.day() as u32
+}
+
+// 两个日期的中点
+pub fn midpoint(from: u32, to: u32) -> Option<u32> {
+    let from = u32_to_date(from).ok()?;
+    let to = u32_to_date(to).ok()?;
+    Some(date_to_u32(from + Duration::days((to - from).whole_days() / 2)))
 }
 
 pub fn check_date(order: &mut Order) -> Result<()> {
@@ -50,7 +109,7 @@ pub fn check_date(order: &mut Order) -> Result<()> {
         || order.draw_date > 99991231
         || order.save_date > order.draw_date
     {
-        bail!("穿越时空？")
+        bail!(tr!("穿越时空？"))
     }
 
     let save_date = u32_to_date(order.save_date)?;
@@ -58,20 +117,171 @@ pub fn check_date(order: &mut Order) -> Result<()> {
 
     order.days = (draw_date.to_julian_day() - save_date.to_julian_day()) as i32;
     if order.days > 36500 {
-        bail!("你确定可以存一个世纪？")
+        bail!(tr!("你确定可以存一个世纪？"))
+    }
+
+    Ok(())
+}
+
+// 只校验主方案的部分支取；改变支取日期的测算中超出范围的部分支取由 calc 忽略
+pub fn check_withdrawal(order: &Order) -> Result<()> {
+    // 零存整取不支持部分支取，忽略遗留的设置
+    if let (OrderMode::LumpSum, Some(withdrawal)) = (order.mode, &order.withdrawal) {
+        u32_to_date(withdrawal.date)?;
+        if withdrawal.date <= order.save_date || withdrawal.date >= order.draw_date {
+            bail!(tr!("部分支取日期要在购买日期与支取日期之间！"))
+        }
+        if withdrawal.amount <= Decimal::ZERO || withdrawal.amount >= order.principal {
+            bail!(tr!("部分支取金额要大于0且小于本金！"))
+        }
     }
 
     Ok(())
 }
 
+// 通知存款的起存金额与通知期
+pub fn check_notice(order: &Order, product: &Product) -> Result<()> {
+    if product.kind != ProductKind::Notice || order.mode != OrderMode::LumpSum {
+        return Ok(());
+    }
+
+    let notice = &product.notice;
+    if order.principal < notice.min_balance {
+        bail!(tr!(
+            "{}最低起存{}元！",
+            notice.period,
+            format!("{:.2}", notice.min_balance)
+        ))
+    }
+    if order.days < notice.period.days() {
+        bail!(tr!(
+            "{}至少存满{}天，不足按活期计息！",
+            notice.period,
+            notice.period.days()
+        ))
+    }
+
+    Ok(())
+}
+
+// 每个存期的计算明细，利息与邦豆利息为截至该期末的累计值
+#[derive(Copy, Clone)]
+pub struct Period {
+    pub start: Date,
+    pub end: Date,
+    pub principal: Decimal,
+    pub interest: Decimal,
+    pub bean_int: Decimal,
+    // 是否存满整个存期（到期续存点）
+    pub matured: bool,
+}
+
+// 存本取息的一次付息，demand_int 为该笔利息到支取日的活期利息
+#[derive(Copy, Clone)]
+pub struct PayoutItem {
+    pub date: Date,
+    pub amount: Decimal,
+    pub demand_int: Decimal,
+}
+
 pub fn calc(req: &Req) -> (Decimal, Decimal) {
-    if req.term < 1 {
-        return (Decimal::ZERO, Decimal::ZERO);
+    let periods = schedule(req);
+    let (interest, bean_int) = periods
+        .last()
+        .map(|p| (p.interest, p.bean_int))
+        .unwrap_or_default();
+
+    let demand_int = payouts(req, &periods)
+        .iter()
+        .fold(Decimal::ZERO, |a, p| a.checked_add(p.demand_int).unwrap_or_default());
+
+    (interest.checked_add(demand_int).unwrap_or_default(), bean_int)
+}
+
+// 把每个到期存期的利息按付息频率拆开；提前支取的存期按活期结算，已付利息从本金扣回，不单独列出
+pub fn payouts(req: &Req, periods: &[Period]) -> Vec<PayoutItem> {
+    let mut items = vec![];
+    let frequency = match (&req.payout.frequency, &req.recurring, req.kind) {
+        (_, _, ProductKind::Notice) => return items,
+        (Some(frequency), None, _) => frequency,
+        _ => return items,
+    };
+    let draw_date = match u32_to_date(req.draw_date) {
+        Ok(draw_date) => draw_date,
+        Err(_) => return items,
+    };
+
+    let mut paid = Decimal::ZERO;
+    for period in periods.iter().filter(|p| p.matured) {
+        let period_int = period.interest - paid;
+        paid = period.interest;
+
+        let days = Decimal::from(period.end.to_julian_day() - period.start.to_julian_day());
+        let mut rest = period_int;
+        let mut start = period.start;
+        let mut k = 1;
+        while start < period.end {
+            let end = min(add_months(period.start, k * frequency.months()), period.end);
+            // 最后一次付息取余额，保证合计等于该期利息
+            let amount = if end == period.end {
+                rest
+            } else {
+                let part = Decimal::from(end.to_julian_day() - start.to_julian_day());
+                part.checked_div(days)
+                    .and_then(|d| d.checked_mul(period_int))
+                    .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+                    .unwrap_or_default()
+            };
+            rest -= amount;
+
+            let demand_int = if req.payout.earn_demand {
+                let held = Decimal::from(draw_date.to_julian_day() - end.to_julian_day());
+                calc_interest(amount, DEMAND_RATE, held)
+                    .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+                    .unwrap_or_default()
+            } else {
+                Decimal::ZERO
+            };
+
+            items.push(PayoutItem {
+                date: end,
+                amount,
+                demand_int,
+            });
+            start = end;
+            k += 1;
+        }
     }
 
+    items
+}
+
+pub fn schedule(req: &Req) -> Vec<Period> {
+    let mut periods = Vec::new();
     let save_date = u32_to_date(req.save_date).unwrap();
     let draw_date = u32_to_date(req.draw_date).unwrap();
 
+    if let Some(recurring) = &req.recurring {
+        return recurring_schedule(req, recurring, save_date, draw_date);
+    }
+
+    if req.kind == ProductKind::Notice {
+        return notice_schedule(req, save_date, draw_date);
+    }
+
+    // 固定到期产品只有一期，不在认购期或到期日有误则没有收益
+    let maturity = match req.kind {
+        ProductKind::Term if req.term < 1 => return periods,
+        ProductKind::Term => None,
+        ProductKind::Fixed => match u32_to_date(req.fixed.maturity) {
+            Ok(maturity) if maturity > save_date && req.fixed.is_open(req.save_date) => {
+                Some(maturity)
+            }
+            _ => return periods,
+        },
+        ProductKind::Notice => unreachable!(),
+    };
+
     let mut start_date = save_date;
     let mut principal = req.principal;
     let mut interest = Decimal::ZERO;
@@ -79,28 +289,24 @@ pub fn calc(req: &Req) -> (Decimal, Decimal) {
     let mut int_rate = req.int_rate;
     let mut bean_rate = req.bean_rate;
 
+    let rules = BeanRules::new(&req.bean_rule, save_date, draw_date);
+    let mut bonus = rules.bonus;
+
+    // 部分提前支取：支取部分按活期计息，剩余本金继续按合同利率计息
+    let mut withdrawal = req
+        .withdrawal
+        .and_then(|w| u32_to_date(w.date).ok().map(|date| (date, w.amount)))
+        .filter(|(date, _)| *date > save_date && *date < draw_date);
+    let mut withdrawn = Decimal::ZERO;
+
     while start_date < draw_date {
-        let mut end_date = match &req.term_type {
-            TermType::D => start_date.saturating_add(Duration::days(req.term as i64)),
-            TermType::M => {
-                let month = start_date.month() as u8 + req.term - 1;
-                let year = start_date.year() + month as i32 / 12;
-                let month = Month::try_from(month % 12 + 1).unwrap();
-                let max_day = util::days_in_year_month(year, month);
-
-                Date::from_calendar_date(year, month, min(start_date.day(), max_day)).unwrap()
-            }
-            TermType::Y => {
-                let year = start_date.year() + req.term as i32;
-                let month = start_date.month();
-                let max_day = util::days_in_year_month(year, month);
-                Date::from_calendar_date(year, month, min(start_date.day(), max_day)).unwrap()
-            }
-        };
+        let mut end_date = maturity.unwrap_or_else(|| term_end(req, start_date));
 
+        let mut matured = true;
         if end_date > draw_date {
             end_date = draw_date;
-            int_rate = Decimal::new(35, 2);
+            matured = false;
+            int_rate = DEMAND_RATE;
             bean_rate = Decimal::ZERO;
         }
 
@@ -109,6 +315,18 @@ pub fn calc(req: &Req) -> (Decimal, Decimal) {
             0,
         );
 
+        if let Some((date, amount)) = withdrawal.filter(|(date, _)| *date < end_date) {
+            let amount = amount.min(principal);
+            let part_days = Decimal::from(date.to_julian_day() - start_date.to_julian_day());
+            interest = calc_interest(amount, DEMAND_RATE, part_days)
+                .and_then(|d| d.checked_add(interest))
+                .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+                .unwrap_or_default();
+            principal -= amount;
+            withdrawn += amount;
+            withdrawal = None;
+        }
+
         // 利息2位小数四舍五入, 溢出归0
         interest = calc_interest(principal, int_rate, days)
             .and_then(|d| d.checked_add(interest))
@@ -116,16 +334,34 @@ pub fn calc(req: &Req) -> (Decimal, Decimal) {
             .unwrap_or_default();
 
         // 邦豆2位小数之后全部舍弃, 溢出归0
-        bean_int = calc_interest(principal, bean_rate, days)
+        bean_int = calc_interest(principal, bean_rate, rules.days(start_date, end_date))
             .and_then(|d| d.checked_add(bean_int))
+            .and_then(|d| d.checked_add(bonus))
             .map(|d| d.round_dp_with_strategy(2, ToZero))
             .unwrap_or_default();
+        bonus = Decimal::ZERO;
+
+        periods.push(Period {
+            start: start_date,
+            end: end_date,
+            principal,
+            interest: principal + withdrawn - req.principal + interest,
+            bean_int: rules.limit(bean_int),
+            matured,
+        });
+
+        // 固定到期产品到期后不续存
+        if maturity.is_some() {
+            break;
+        }
 
         match req.renew_type {
             RenewType::N => {
                 break;
             }
             RenewType::P => {}
+            // 存本取息的利息已付出，不能转入本金
+            RenewType::I if req.payout.frequency.is_some() => {}
             RenewType::I => {
                 principal = principal.checked_add(interest).unwrap_or_default();
                 interest = Decimal::ZERO;
@@ -134,7 +370,412 @@ pub fn calc(req: &Req) -> (Decimal, Decimal) {
         start_date = end_date;
     }
 
-    (principal - req.principal + interest, bean_int)
+    periods
+}
+
+// 通知存款按实际存款天数和通知利率一次计息，不足通知期按活期计息且只保留首存奖励，
+// 低于起存金额没有收益
+fn notice_schedule(req: &Req, save_date: Date, draw_date: Date) -> Vec<Period> {
+    if req.principal < req.notice.min_balance || save_date >= draw_date {
+        return vec![];
+    }
+
+    let held = draw_date.to_julian_day() - save_date.to_julian_day();
+    let matured = held >= req.notice.period.days();
+    let (int_rate, bean_rate) = if matured {
+        (req.int_rate, req.bean_rate)
+    } else {
+        (DEMAND_RATE, Decimal::ZERO)
+    };
+    let days = Decimal::from(held);
+    let rules = BeanRules::new(&req.bean_rule, save_date, draw_date);
+
+    let interest = calc_interest(req.principal, int_rate, days)
+        .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+        .unwrap_or_default();
+    let bean_int = calc_interest(req.principal, bean_rate, rules.days(save_date, draw_date))
+        .and_then(|d| d.checked_add(rules.bonus))
+        .map(|d| d.round_dp_with_strategy(2, ToZero))
+        .unwrap_or_default();
+
+    vec![Period {
+        start: save_date,
+        end: draw_date,
+        principal: req.principal,
+        interest,
+        bean_int: rules.limit(bean_int),
+        matured,
+    }]
+}
+
+// 邦豆活动规则，三种计息方式共用
+struct BeanRules {
+    // 持有天数不足则不发放邦豆（含首存奖励）
+    qualified: bool,
+    until: Option<Date>,
+    // 活动期内存入才有首存奖励
+    bonus: Decimal,
+    cap: Decimal,
+}
+
+impl BeanRules {
+    fn new(rule: &BeanRule, save_date: Date, draw_date: Date) -> Self {
+        let until = match rule.valid_until {
+            0 => None,
+            v => u32_to_date(v).ok(),
+        };
+        Self {
+            qualified: draw_date.to_julian_day() - save_date.to_julian_day()
+                >= rule.min_days as i32,
+            until,
+            bonus: match until {
+                Some(until) if save_date > until => Decimal::ZERO,
+                _ => rule.bonus,
+            },
+            cap: rule.cap,
+        }
+    }
+
+    // 活动截止后的天数不计邦豆
+    fn days(&self, start: Date, end: Date) -> Decimal {
+        let end = match self.until {
+            Some(until) if until < end => until,
+            _ => end,
+        };
+        Decimal::from((end.to_julian_day() - start.to_julian_day()).max(0))
+    }
+
+    // 累计邦豆利息按资格与上限调整
+    fn limit(&self, bean_int: Decimal) -> Decimal {
+        if !self.qualified {
+            Decimal::ZERO
+        } else if self.cap > Decimal::ZERO {
+            bean_int.min(self.cap)
+        } else {
+            bean_int
+        }
+    }
+}
+
+// 大额存单转让估值结果
+pub struct CdValue {
+    // 到期本息
+    pub maturity_value: Decimal,
+    // 转让日已计利息
+    pub accrued: Decimal,
+    // 按买方收益率折现的公允转让价
+    pub price: Decimal,
+    // 卖方持有期间的实际年化收益率(%)
+    pub seller_yield: Decimal,
+    pub held_days: i32,
+    pub left_days: i32,
+}
+
+pub fn value_cd(cd: &Cd) -> Result<CdValue> {
+    let issue_date = u32_to_date(cd.issue_date)?;
+    let maturity = u32_to_date(cd.maturity)?;
+    let transfer_date = u32_to_date(cd.transfer_date)?;
+    if issue_date >= maturity {
+        bail!(tr!("到期日要晚于起息日！"))
+    }
+    if transfer_date <= issue_date || transfer_date >= maturity {
+        bail!(tr!("转让日期要在起息日与到期日之间！"))
+    }
+    if cd.face <= Decimal::ZERO {
+        bail!(tr!("面额要大于0！"))
+    }
+
+    let days = |from: Date, to: Date| to.to_julian_day() - from.to_julian_day();
+    let total_days = days(issue_date, maturity);
+    let held_days = days(issue_date, transfer_date);
+    let left_days = days(transfer_date, maturity);
+    let overflow = || anyhow!(tr!("金额太大，算不过来了！"));
+
+    let maturity_value = calc_interest(cd.face, cd.rate, Decimal::from(total_days))
+        .and_then(|d| d.checked_add(cd.face))
+        .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+        .ok_or_else(overflow)?;
+    let accrued = calc_interest(cd.face, cd.rate, Decimal::from(held_days))
+        .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+        .ok_or_else(overflow)?;
+
+    // 转让价 = 到期本息 / (1 + 买方收益率 × 剩余天数 / 360)
+    let price = calc_interest(Decimal::ONE, cd.buyer_yield, Decimal::from(left_days))
+        .and_then(|d| d.checked_add(Decimal::ONE))
+        .and_then(|d| maturity_value.checked_div(d))
+        .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+        .ok_or_else(overflow)?;
+
+    // 卖方收益率 = (转让价 - 面额) / 面额 × 360 / 持有天数
+    let seller_yield = (price - cd.face)
+        .checked_div(cd.face)
+        .and_then(|d| d.checked_mul(Decimal::new(360, 0)))
+        .and_then(|d| d.checked_div(Decimal::from(held_days)))
+        .and_then(|d| d.checked_mul(Decimal::ONE_HUNDRED))
+        .map(|d| d.round_dp_with_strategy(4, MidpointAwayFromZero))
+        .ok_or_else(overflow)?;
+
+    Ok(CdValue {
+        maturity_value,
+        accrued,
+        price,
+        seller_yield,
+        held_days,
+        left_days,
+    })
+}
+
+// 扣除通胀后的收益，金额折算为购买日的购买力
+pub struct RealReturn {
+    pub interest: Decimal,
+    // 实际年化收益率(%)，按每笔资金占用的天数加权
+    pub yield_rate: Decimal,
+}
+
+// total 为名义总收益，每笔存入和取出按各自日期的价格指数折算
+pub fn real_return(order: &Order, inflation: &Inflation, total: Decimal) -> Option<RealReturn> {
+    let save_date = u32_to_date(order.save_date).ok()?;
+    let draw_date = u32_to_date(order.draw_date).ok()?;
+    if draw_date <= save_date {
+        return None;
+    }
+
+    // 存入为正，部分提前支取为负
+    let mut flows = vec![];
+    match order.mode {
+        OrderMode::LumpSum => {
+            flows.push((save_date, order.principal));
+            if let Some(withdrawal) = order.withdrawal {
+                flows.push((u32_to_date(withdrawal.date).ok()?, -withdrawal.amount));
+            }
+        }
+        OrderMode::Recurring => {
+            let months = order.recurring.frequency.months();
+            for k in 0..order.recurring.count as i32 {
+                let date = add_months(save_date, k * months);
+                if date >= draw_date {
+                    break;
+                }
+                flows.push((date, order.recurring.amount));
+            }
+        }
+    }
+
+    let mut end_value = total;
+    let mut real_in = Decimal::ZERO;
+    let mut weighted = Decimal::ZERO;
+    for (date, amount) in flows {
+        let days = Decimal::from(draw_date.to_julian_day() - date.to_julian_day());
+        let index = price_index(inflation, save_date, date)?;
+        end_value = end_value.checked_add(amount)?;
+        real_in = real_in.checked_add(amount.checked_div(index)?)?;
+        weighted = weighted.checked_add(amount.checked_mul(days)?)?;
+    }
+
+    let interest = end_value
+        .checked_div(price_index(inflation, save_date, draw_date)?)?
+        .checked_sub(real_in)?
+        .round_dp_with_strategy(2, ToZero);
+    if weighted <= Decimal::ZERO {
+        return None;
+    }
+    let yield_rate = interest
+        .checked_mul(Decimal::new(360, 0))
+        .and_then(|d| d.checked_div(weighted))
+        .and_then(|d| d.checked_mul(Decimal::ONE_HUNDRED))
+        .map(|d| d.round_dp_with_strategy(4, MidpointAwayFromZero))?;
+
+    Some(RealReturn {
+        interest,
+        yield_rate,
+    })
+}
+
+// 两个日期之间价格指数的倍数，通胀率按日复利，每段使用其起始日适用的年通胀率
+fn price_index(inflation: &Inflation, from: Date, to: Date) -> Option<Decimal> {
+    let mut bounds: Vec<Date> = inflation
+        .series
+        .iter()
+        .filter_map(|cpi| u32_to_date(cpi.date).ok())
+        .filter(|date| *date > from && *date < to)
+        .collect();
+    bounds.push(to);
+
+    let mut exponent = 0f64;
+    let mut start = from;
+    for end in bounds {
+        let rate = inflation.rate_on(date_to_u32(start)).to_f64()?;
+        let days = (end - start).whole_days() as f64;
+        exponent += days / 365.0 * (1.0 + rate / 100.0).ln();
+        start = end;
+    }
+    Decimal::from_f64(exponent.exp())
+}
+
+// 零存整取按月积数计息：利息 = 每期金额 × 累计月积数 × 月利率，
+// 到期前支取则每笔存款按活期利率和实际天数计息，只保留首存奖励
+fn recurring_schedule(
+    req: &Req,
+    recurring: &Recurring,
+    save_date: Date,
+    draw_date: Date,
+) -> Vec<Period> {
+    let mut periods = Vec::new();
+    let count = recurring.count as i32;
+    let months = recurring.frequency.months();
+    if count < 1 {
+        return periods;
+    }
+
+    let dates: Vec<Date> = (0..=count)
+        .map(|k| add_months(save_date, k * months))
+        .collect();
+    let maturity = dates[count as usize];
+
+    let rules = BeanRules::new(&req.bean_rule, save_date, draw_date);
+
+    for k in 0..count as usize {
+        let start_date = dates[k];
+        if start_date >= draw_date {
+            break;
+        }
+        let deposits = Decimal::from(k + 1);
+        let principal = recurring.amount.checked_mul(deposits).unwrap_or_default();
+
+        if maturity <= draw_date {
+            // 累计月积数 = 频率 × (1 + 2 + … + 已存期数)
+            let balance_months = Decimal::from(months as usize * (k + 1) * (k + 2) / 2);
+            let interest = calc_monthly(recurring.amount, req.int_rate, balance_months)
+                .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+                .unwrap_or_default();
+            // 每笔存款的月积数按活动截止前的天数比例计入
+            let bean_months = (0..=k)
+                .map(|j| {
+                    let full =
+                        Decimal::from(dates[k + 1].to_julian_day() - dates[j].to_julian_day());
+                    Decimal::from(months as usize * (k + 1 - j))
+                        .checked_mul(rules.days(dates[j], dates[k + 1]))
+                        .and_then(|d| d.checked_div(full))
+                        .unwrap_or_default()
+                })
+                .fold(Decimal::ZERO, |a, b| a.checked_add(b).unwrap_or_default());
+            let bean_int = calc_monthly(recurring.amount, req.bean_rate, bean_months)
+                .and_then(|d| d.checked_add(rules.bonus))
+                .map(|d| d.round_dp_with_strategy(2, ToZero))
+                .unwrap_or_default();
+
+            periods.push(Period {
+                start: start_date,
+                end: dates[k + 1],
+                principal,
+                interest,
+                bean_int: rules.limit(bean_int),
+                matured: k + 1 == count as usize,
+            });
+        } else {
+            let end_date = min(dates[k + 1], draw_date);
+            let interest = dates[..=k]
+                .iter()
+                .map(|d| {
+                    let days = Decimal::from(end_date.to_julian_day() - d.to_julian_day());
+                    calc_interest(recurring.amount, DEMAND_RATE, days).unwrap_or_default()
+                })
+                .fold(Decimal::ZERO, |a, b| a.checked_add(b).unwrap_or_default())
+                .round_dp_with_strategy(2, MidpointAwayFromZero);
+
+            periods.push(Period {
+                start: start_date,
+                end: end_date,
+                principal,
+                interest,
+                bean_int: rules.limit(rules.bonus),
+                matured: false,
+            });
+        }
+    }
+
+    // 到期后未支取的部分按活期计息
+    if let Some(last) = periods.last().copied().filter(|_| maturity < draw_date) {
+        let days = Decimal::from(draw_date.to_julian_day() - maturity.to_julian_day());
+        let interest = calc_interest(last.principal, DEMAND_RATE, days)
+            .and_then(|d| d.checked_add(last.interest))
+            .map(|d| d.round_dp_with_strategy(2, MidpointAwayFromZero))
+            .unwrap_or_default();
+        periods.push(Period {
+            start: maturity,
+            end: draw_date,
+            interest,
+            matured: false,
+            ..last
+        });
+    }
+
+    periods
+}
+
+// 首次到期日，通知存款没有到期日
+pub fn first_maturity(req: &Req) -> Option<Date> {
+    let save_date = u32_to_date(req.save_date).ok()?;
+    match req.kind {
+        ProductKind::Term if req.term < 1 => None,
+        ProductKind::Term => Some(term_end(req, save_date)),
+        ProductKind::Fixed => u32_to_date(req.fixed.maturity)
+            .ok()
+            .filter(|maturity| *maturity > save_date),
+        ProductKind::Notice => None,
+    }
+}
+
+// 支取日期前的各个到期日，不续存或固定到期的产品只有一个
+pub fn maturities(req: &Req) -> Vec<Date> {
+    let mut dates = vec![];
+    let (mut date, draw_date) = match (first_maturity(req), u32_to_date(req.draw_date)) {
+        (Some(date), Ok(draw_date)) => (date, draw_date),
+        _ => return dates,
+    };
+    while date <= draw_date {
+        dates.push(date);
+        if req.kind != ProductKind::Term || req.renew_type == RenewType::N {
+            break;
+        }
+        let next = term_end(req, date);
+        if next <= date {
+            break;
+        }
+        date = next;
+    }
+    dates
+}
+
+fn term_end(req: &Req, start_date: Date) -> Date {
+    let term = req.term as i32;
+    match req.term_type {
+        TermType::D => start_date.saturating_add(Duration::days(term as i64)),
+        TermType::W => start_date.saturating_add(Duration::weeks(term as i64)),
+        TermType::M => add_months(start_date, term),
+        TermType::Q => add_months(start_date, term * 3),
+        TermType::H => add_months(start_date, term * 6),
+        TermType::Y => add_months(start_date, term * 12),
+    }
+}
+
+// 到期日为若干月后的同一天，没有这一天则取当月最后一天
+fn add_months(date: Date, months: i32) -> Date {
+    let month = date.month() as i32 + months - 1;
+    let year = date.year() + month / 12;
+    let month = Month::try_from((month % 12 + 1) as u8).unwrap();
+    let max_day = util::days_in_year_month(year, month);
+
+    Date::from_calendar_date(year, month, min(date.day(), max_day))
+        .unwrap_or(Date::MAX)
+}
+
+fn calc_monthly(amount: Decimal, rate: Decimal, months: Decimal) -> Option<Decimal> {
+    months
+        .checked_div(Decimal::new(12, 0))
+        .and_then(|d| d.checked_mul(rate))
+        .and_then(|d| d.checked_div(Decimal::ONE_HUNDRED))
+        .and_then(|d| d.checked_mul(amount))
 }
 
 fn calc_interest(principal: Decimal, rate: Decimal, days: Decimal) -> Option<Decimal> {
@@ -143,3 +784,266 @@ fn calc_interest(principal: Decimal, rate: Decimal, days: Decimal) -> Option<Dec
         .and_then(|d| d.checked_div(Decimal::ONE_HUNDRED))
         .and_then(|d| d.checked_mul(principal))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::config::{Cpi, Frequency, NoticePeriod};
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn order(principal: &str, save_date: u32, draw_date: u32) -> Order {
+        let mut order = Order {
+            principal: dec(principal),
+            save_date,
+            draw_date,
+            ..Order::default()
+        };
+        check_date(&mut order).unwrap();
+        order
+    }
+
+    fn product(term: u16, term_type: TermType, int_rate: &str, renew_type: RenewType) -> Product {
+        Product {
+            term,
+            term_type,
+            int_rate: dec(int_rate),
+            renew_type,
+            ..Product::default()
+        }
+    }
+
+    fn one_year() -> Product {
+        product(1, TermType::Y, "2.00", RenewType::N)
+    }
+
+    fn recurring(draw_date: u32) -> Req {
+        let order = Order {
+            mode: OrderMode::Recurring,
+            recurring: Recurring {
+                amount: dec("1000"),
+                frequency: Frequency::M,
+                count: 12,
+            },
+            ..order("0", 20240101, draw_date)
+        };
+        Req::new(&order, &product(1, TermType::Y, "1.50", RenewType::N))
+    }
+
+    #[test]
+    fn recurring_by_monthly_balance() {
+        // 月积数 1+2+…+12 = 78，1000 × 78 / 12 × 1.5%
+        assert_eq!(calc(&recurring(20250101)).0, dec("97.50"));
+    }
+
+    #[test]
+    fn recurring_demand_after_maturity() {
+        // 到期后 31 天，12000 按活期计息 3.62
+        assert_eq!(calc(&recurring(20250201)).0, dec("101.12"));
+    }
+
+    #[test]
+    fn recurring_drawn_early() {
+        // 两笔存款按活期分别计息 60 天与 29 天
+        assert_eq!(calc(&recurring(20240301)).0, dec("0.87"));
+    }
+
+    #[test]
+    fn notice_deposit() {
+        let mut notice = product(0, TermType::D, "1.00", RenewType::N);
+        notice.kind = ProductKind::Notice;
+        notice.notice.period = NoticePeriod::D7;
+
+        let req = Req::new(&order("50000", 20240101, 20240131), &notice);
+        assert_eq!(calc(&req).0, dec("41.67"));
+
+        // 不足通知期按活期
+        let req = Req::new(&order("50000", 20240101, 20240106), &notice);
+        assert_eq!(calc(&req).0, dec("2.43"));
+
+        // 低于起存金额没有收益
+        let req = Req::new(&order("49999", 20240101, 20240131), &notice);
+        assert_eq!(calc(&req), (Decimal::ZERO, Decimal::ZERO));
+    }
+
+    #[test]
+    fn partial_withdrawal() {
+        // 40000 持有 182 天按活期 70.78，其余 60000 按 2% 持有 366 天 1220.00
+        let order = Order {
+            withdrawal: Some(Withdrawal {
+                date: 20240701,
+                amount: dec("40000"),
+            }),
+            ..order("100000", 20240101, 20250101)
+        };
+        check_withdrawal(&order).unwrap();
+        assert_eq!(calc(&Req::new(&order, &one_year())).0, dec("1290.78"));
+    }
+
+    #[test]
+    fn withdrawal_out_of_range() {
+        // 支取日期早于部分支取日期时日期本身有效，计算忽略部分支取
+        let mut order = Order {
+            withdrawal: Some(Withdrawal {
+                date: 20250301,
+                amount: dec("40000"),
+            }),
+            ..order("100000", 20240101, 20250101)
+        };
+        assert!(check_date(&mut order).is_ok());
+        assert!(check_withdrawal(&order).is_err());
+
+        let without = Order {
+            withdrawal: None,
+            ..order
+        };
+        assert_eq!(
+            calc(&Req::new(&order, &one_year())),
+            calc(&Req::new(&without, &one_year()))
+        );
+        assert_eq!(calc(&Req::new(&order, &one_year())).0, dec("2033.33"));
+    }
+
+    #[test]
+    fn monthly_payout() {
+        let mut product = one_year();
+        product.payout.frequency = Some(Frequency::M);
+        product.payout.earn_demand = true;
+        let req = Req::new(&order("100000", 20240101, 20250101), &product);
+
+        let periods = schedule(&req);
+        let items = payouts(&req, &periods);
+        assert_eq!(items.len(), 12);
+        // 按天数拆分，最后一次取余额
+        assert_eq!(items[0].amount, dec("172.22"));
+        assert_eq!(items.iter().map(|p| p.amount).sum::<Decimal>(), dec("2033.33"));
+        // 2月1日付出的利息按活期再计 335 天
+        assert_eq!(items[0].demand_int, dec("0.56"));
+
+        let demand_int: Decimal = items.iter().map(|p| p.demand_int).sum();
+        assert_eq!(calc(&req).0, dec("2033.33") + demand_int);
+    }
+
+    #[test]
+    fn bean_rules() {
+        let bean = |rule: BeanRule| {
+            let product = Product {
+                bean_rate: dec("3.00"),
+                bean_rule: rule,
+                ..one_year()
+            };
+            calc(&Req::new(&order("100000", 20240101, 20250101), &product)).1
+        };
+
+        assert_eq!(bean(BeanRule::default()), dec("3050.00"));
+        assert_eq!(
+            bean(BeanRule {
+                cap: dec("1000"),
+                ..BeanRule::default()
+            }),
+            dec("1000")
+        );
+        assert_eq!(
+            bean(BeanRule {
+                bonus: dec("10"),
+                ..BeanRule::default()
+            }),
+            dec("3060.00")
+        );
+        assert_eq!(
+            bean(BeanRule {
+                bonus: dec("10"),
+                min_days: 400,
+                ..BeanRule::default()
+            }),
+            Decimal::ZERO
+        );
+        // 活动截止后的 184 天不计邦豆
+        assert_eq!(
+            bean(BeanRule {
+                valid_until: 20240701,
+                ..BeanRule::default()
+            }),
+            dec("1516.66")
+        );
+    }
+
+    #[test]
+    fn cd_transfer() {
+        let cd = Cd {
+            face: dec("200000"),
+            rate: dec("3.10"),
+            issue_date: 20240101,
+            maturity: 20250101,
+            transfer_date: 20240701,
+            buyer_yield: dec("3.00"),
+        };
+        let value = value_cd(&cd).unwrap();
+        assert_eq!(value.maturity_value, dec("206303.33"));
+        assert_eq!(value.accrued, dec("3134.44"));
+        assert_eq!(value.price, dec("203187.78"));
+        assert_eq!(value.seller_yield, dec("3.1527"));
+        assert_eq!((value.held_days, value.left_days), (182, 184));
+
+        assert!(value_cd(&Cd {
+            transfer_date: cd.issue_date,
+            ..cd
+        })
+        .is_err());
+        assert!(value_cd(&Cd::default()).is_ok());
+    }
+
+    #[test]
+    fn real_return_rate() {
+        let order = order("100000", 20240101, 20250101);
+        let mut inflation = Inflation {
+            enabled: true,
+            rate: Decimal::ZERO,
+            series: vec![],
+        };
+        let real = real_return(&order, &inflation, dec("2033.33")).unwrap();
+        assert_eq!(real.interest, dec("2033.33"));
+        assert_eq!(real.yield_rate, dec("2.0000"));
+
+        inflation.rate = dec("2.00");
+        let real = real_return(&order, &inflation, dec("2033.33")).unwrap();
+        assert_eq!(real.interest, dec("27.24"));
+        assert_eq!(real.yield_rate, dec("0.0268"));
+
+        // 序列从购买日起生效时与固定通胀率相同
+        inflation.rate = Decimal::ZERO;
+        inflation.series = vec![Cpi {
+            date: 20240101,
+            rate: dec("2.00"),
+        }];
+        let real = real_return(&order, &inflation, dec("2033.33")).unwrap();
+        assert_eq!(real.interest, dec("27.24"));
+    }
+
+    #[test]
+    fn last_representable_date() {
+        let mut order = order("100000", 99900101, 99991231);
+        assert!(check_date(&mut order).is_ok());
+        assert_eq!(order.days, 3651);
+
+        let req = Req::new(
+            &Order {
+                save_date: 99991201,
+                ..order
+            },
+            &product(7, TermType::D, "1.85", RenewType::P),
+        );
+        let dates = maturities(&req);
+        assert_eq!(dates.first().map(|d| date_to_u32(*d)), Some(99991208));
+        assert!(dates.iter().all(|d| date_to_u32(*d) <= 99991231));
+        assert!(calc(&req).0 > Decimal::ZERO);
+    }
+
+    #[test]
+    fn midpoint_date() {
+        assert_eq!(midpoint(20240101, 20250101), Some(20240702));
+        assert_eq!(midpoint(20240101, 20240101), Some(20240101));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+use eframe::egui::plot::{Legend, Line, MarkerShape, Plot, Points, Value, Values};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::app::calculator::{self, Req};
+use crate::app::i18n::tr;
+use crate::app::App;
+
+// 一个产品的曲线，输入不变时重复使用，不必每帧重算明细
+pub struct Series {
+    interest: Vec<Value>,
+    bean_int: Vec<Value>,
+    rollover: Vec<Value>,
+}
+
+impl Series {
+    fn new(req: &Req) -> Self {
+        let save_date = calculator::u32_to_date(req.save_date).unwrap();
+        let periods = calculator::schedule(req);
+
+        // 单利在存期内线性增长，取每期起止点即可
+        let mut interest = vec![Value::new(0.0, 0.0)];
+        let mut bean_int = vec![Value::new(0.0, 0.0)];
+        let mut rollover = vec![];
+        for period in &periods {
+            let x = (period.end - save_date).whole_days() as f64;
+            interest.push(Value::new(x, to_f64(period.interest)));
+            bean_int.push(Value::new(x, to_f64(period.bean_int)));
+            if period.matured {
+                rollover.push(Value::new(x, to_f64(period.interest)));
+            }
+        }
+
+        Self {
+            interest,
+            bean_int,
+            rollover,
+        }
+    }
+}
+
+impl App {
+    pub(super) fn show_chart(&mut self, ctx: &egui::Context) {
+        let mut open = self.chart_open;
+        egui::Window::new(tr!("收益走势"))
+            .id(egui::Id::new("chart"))
+            .open(&mut open)
+            .default_size([640.0, 360.0])
+            .show(ctx, |ui| {
+                let mut order = self.cfg.order;
+                if calculator::check_date(&mut order).is_err() {
+                    ui.label(tr!("日期有误，无法绘制"));
+                    return;
+                }
+                if !self.cfg.products.iter().any(|p| p.selected) {
+                    ui.label(tr!("请在产品列表中勾选需要绘制的产品"));
+                    return;
+                }
+
+                let products: Vec<_> = self
+                    .cfg
+                    .products
+                    .iter()
+                    .filter(|p| p.selected)
+                    .map(|p| (p.name(), Req::new(&order, p)))
+                    .collect();
+                // 只保留当前勾选产品的曲线，新的输入才重新计算
+                let series: HashMap<Req, Series> = products
+                    .iter()
+                    .map(|(_, req)| {
+                        let s = self
+                            .chart_series
+                            .remove(req)
+                            .unwrap_or_else(|| Series::new(req));
+                        (*req, s)
+                    })
+                    .collect();
+
+                Plot::new("收益走势图")
+                    .legend(Legend::default())
+                    .show(ui, |plot_ui| {
+                        for (name, req) in &products {
+                            let s = &series[req];
+                            plot_ui.line(
+                                Line::new(Values::from_values(s.interest.clone()))
+                                    .name(tr!("{} 利息", name)),
+                            );
+                            plot_ui.line(
+                                Line::new(Values::from_values(s.bean_int.clone()))
+                                    .name(tr!("{} 邦豆利息", name)),
+                            );
+                            plot_ui.points(
+                                Points::new(Values::from_values(s.rollover.clone()))
+                                    .shape(MarkerShape::Circle)
+                                    .radius(3.0)
+                                    .name(tr!("{} 到期", name)),
+                            );
+                        }
+                    });
+                self.chart_series = series;
+            });
+        self.chart_open = open;
+    }
+}
+
+fn to_f64(d: Decimal) -> f64 {
+    d.to_f64().unwrap_or_default()
+}
@@ -0,0 +1,60 @@
+use crate::app::config::Product;
+use crate::app::i18n::tr;
+
+// 复制到剪贴板的格式，TSV 可直接粘贴到表格软件，Markdown 用于聊天和邮件
+#[derive(Copy, Clone)]
+pub enum Format {
+    Tsv,
+    Markdown,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Tsv, Format::Markdown];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Tsv => "TSV",
+            Format::Markdown => "Markdown",
+        }
+    }
+}
+
+// 带表头的产品表
+pub fn table<'a>(products: impl IntoIterator<Item = &'a Product>, format: Format) -> String {
+    let rows: Vec<[String; 6]> = products.into_iter().map(row).collect();
+    let header = [
+        tr!("存期"),
+        tr!("利率(%)"),
+        tr!("邦豆利率(%)"),
+        tr!("续存类型"),
+        tr!("利息"),
+        tr!("邦豆利息"),
+    ];
+    let mut text = line(&header.map(str::to_owned), format);
+    if let Format::Markdown = format {
+        text.push_str(&line(&header.map(|_| "---".to_owned()), format));
+    }
+    rows.iter().for_each(|r| text.push_str(&line(r, format)));
+    text
+}
+
+fn row(p: &Product) -> [String; 6] {
+    [
+        p.term_label(),
+        format!("{:.2}", p.int_rate),
+        format!("{:.2}", p.bean_rate),
+        p.renew_type.to_string(),
+        format!("{:.2}", p.interest),
+        format!("{:.2}", p.bean_int),
+    ]
+}
+
+fn line(cells: &[String], format: Format) -> String {
+    match format {
+        Format::Tsv => format!("{}\n", cells.join("\t")),
+        Format::Markdown => {
+            let cells: Vec<String> = cells.iter().map(|c| c.replace('|', "\\|")).collect();
+            format!("| {} |\n", cells.join(" | "))
+        }
+    }
+}
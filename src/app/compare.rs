@@ -0,0 +1,210 @@
+use std::iter::once;
+
+use anyhow::anyhow;
+use eframe::egui;
+use eframe::egui::{Color32, RichText, ScrollArea, TextEdit, Widget};
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy::ToZero;
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::Order;
+use crate::app::history::Field;
+use crate::app::i18n::tr;
+use crate::app::worker::Lane;
+use crate::app::App;
+
+impl App {
+    pub(super) fn show_compare(&mut self, ctx: &egui::Context) {
+        let mut open = self.compare_open;
+        egui::Window::new(tr!("方案对比"))
+            .id(egui::Id::new("compare"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                self.scenario_editor(ui);
+                if let Some(e) = &self.compare_error {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+                ui.separator();
+                self.compare_table(ui);
+            });
+        self.compare_open = open;
+    }
+
+    fn scenario_editor(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+
+        egui::Grid::new("方案列表").striped(true).show(ui, |ui| {
+            ui.label(tr!("方案"));
+            ui.label(tr!("金额"));
+            ui.label(tr!("购买日期"));
+            ui.label(tr!("支取日期"));
+            ui.label(tr!("天数"));
+            if ui.button(tr!("添加")).clicked() {
+                self.edit(Field::Other);
+                self.cfg.scenarios.push(self.cfg.order);
+            }
+            ui.end_row();
+
+            for i in 0..self.cfg.scenarios.len() {
+                ui.label(tr!("方案{}", i + 1));
+
+                // 零存整取的方案编辑每期金额
+                let order = self.cfg.scenarios[i];
+                let mut principal = format!("{:.2}", order.amount());
+                if TextEdit::singleline(&mut principal)
+                    .desired_width(100.0)
+                    .ui(ui)
+                    .on_hover_text(order.amount_label())
+                    .changed()
+                {
+                    self.scenario_principal_changed(&*principal, i);
+                }
+
+                let mut save_date = self.cfg.scenarios[i].save_date.to_string();
+                if TextEdit::singleline(&mut save_date)
+                    .desired_width(80.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    save_date.truncate(8);
+                    self.scenario_save_date_changed(&*save_date, i);
+                }
+
+                let mut draw_date = self.cfg.scenarios[i].draw_date.to_string();
+                if TextEdit::singleline(&mut draw_date)
+                    .desired_width(80.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    draw_date.truncate(8);
+                    self.scenario_draw_date_changed(&*draw_date, i);
+                }
+
+                ui.label(self.cfg.scenarios[i].days.to_string());
+
+                if ui.button(tr!("删除")).clicked() {
+                    removed = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = removed {
+            self.edit(Field::Other);
+            self.cfg.scenarios.remove(i);
+        }
+    }
+
+    fn compare_table(&mut self, ui: &mut egui::Ui) {
+        // 第一个是当前方案，其余为对比方案；日期有误的方案不参与计算
+        let orders: Vec<Option<Order>> = once(self.cfg.order)
+            .chain(self.cfg.scenarios.iter().copied())
+            .map(|mut order| calculator::check_date(&mut order).ok().map(|_| order))
+            .collect();
+        let products = self.cfg.products.clone();
+
+        let reqs = orders
+            .iter()
+            .flatten()
+            .flat_map(|o| products.iter().map(|p| Req::new(o, p)))
+            .collect();
+        self.request(Lane::Compare, reqs);
+        let value = self.cfg.bean_value;
+        let batches = &self.batches;
+        let lookup = |order: &Option<Order>, product| {
+            order.and_then(|o| batches.get(Lane::Compare, &Req::new(&o, product)))
+        };
+
+        ScrollArea::horizontal().show(ui, |ui| {
+            egui::Grid::new("方案对比表").striped(true).show(ui, |ui| {
+                ui.label(tr!("存期"));
+                ui.label(tr!("续存类型"));
+                ui.label(tr!("当前利息"));
+                ui.label(tr!("当前邦豆利息"));
+                for i in 1..orders.len() {
+                    ui.label(tr!("方案{}利息", i));
+                    ui.label(tr!("方案{}邦豆利息", i));
+                    ui.label(tr!("方案{}差额", i));
+                }
+                ui.end_row();
+
+                for product in &products {
+                    ui.label(product.term_label());
+                    ui.label(product.renew_type.to_string());
+
+                    let base = lookup(&orders[0], product);
+                    result_cells(ui, orders[0].is_some(), base);
+
+                    for order in &orders[1..] {
+                        let res = lookup(order, product);
+                        result_cells(ui, order.is_some(), res);
+
+                        match (base, res) {
+                            (Some(b), Some(r)) => {
+                                ui.label(delta_text(value.total(r.0, r.1) - value.total(b.0, b.1)));
+                            }
+                            _ => {
+                                ui.label("-");
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn scenario_principal_changed(&mut self, principal: &str, index: usize) {
+        if let Ok(mut v) = principal.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::new(1000_0000_0000, 0) {
+                self.compare_error = Some(anyhow!(tr!("一千亿啊，土豪，还需要算吗？")))
+            } else {
+                self.compare_error = None;
+                self.edit(Field::Scenario(index));
+                self.cfg.scenarios[index].set_amount(v);
+            }
+        }
+    }
+
+    fn scenario_save_date_changed(&mut self, save_date: &str, index: usize) {
+        if let Ok(v) = save_date.parse() {
+            self.edit(Field::Scenario(index));
+            self.cfg.scenarios[index].save_date = v;
+            calculator::check_date(&mut self.cfg.scenarios[index]).ok();
+        }
+    }
+
+    fn scenario_draw_date_changed(&mut self, draw_date: &str, index: usize) {
+        if let Ok(v) = draw_date.parse() {
+            self.edit(Field::Scenario(index));
+            self.cfg.scenarios[index].draw_date = v;
+            calculator::check_date(&mut self.cfg.scenarios[index]).ok();
+        }
+    }
+}
+
+fn result_cells(ui: &mut egui::Ui, valid: bool, res: Option<(Decimal, Decimal)>) {
+    match res {
+        Some((interest, bean_int)) => {
+            ui.label(format!("{:.2}", interest));
+            ui.label(format!("{:.2}", bean_int));
+        }
+        None => {
+            let text = if valid { tr!("计算中") } else { tr!("日期有误") };
+            ui.label(text);
+            ui.label(text);
+        }
+    }
+}
+
+// 总收益的差额，增加为绿色，减少为红色
+fn delta_text(delta: Decimal) -> RichText {
+    if delta.is_sign_negative() && !delta.is_zero() {
+        RichText::new(format!("{:.2}", delta)).color(Color32::RED)
+    } else if delta.is_zero() {
+        RichText::new(format!("{:.2}", delta))
+    } else {
+        RichText::new(format!("+{:.2}", delta)).color(Color32::GREEN)
+    }
+}
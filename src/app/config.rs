@@ -1,13 +1,31 @@
 use std::fmt::{Display, Formatter};
 
 use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy::ToZero;
 use serde::{Deserialize, Serialize};
 use time::{OffsetDateTime, UtcOffset};
 
+use crate::app::calculator;
+use crate::app::i18n::{self, tr};
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
     pub order: Order,
     pub products: Vec<Product>,
+    // 用于对比的其他方案，与当前方案共用产品列表
+    #[serde(default)]
+    pub scenarios: Vec<Order>,
+    #[serde(default)]
+    pub bean_value: BeanValue,
+    // 大额存单转让估值
+    #[serde(default)]
+    pub cd: Cd,
+    // 各银行的存款，用于存款保险额度检查
+    #[serde(default)]
+    pub portfolio: Portfolio,
+    // 通胀假设，用于计算实际收益
+    #[serde(default)]
+    pub inflation: Inflation,
 }
 
 impl Default for Config {
@@ -28,16 +46,194 @@ impl Default for Config {
                 Product::new(5, TermType::Y, 365, 200, RenewType::P),
                 Product::new(5, TermType::Y, 365, 200, RenewType::I),
             ],
+            scenarios: vec![],
+            bean_value: BeanValue::default(),
+            cd: Cd::default(),
+            portfolio: Portfolio::default(),
+            inflation: Inflation::default(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Inflation {
+    pub enabled: bool,
+    // 年通胀率(%)，没有载入序列或早于序列起始日期时使用
+    pub rate: Decimal,
+    // 按日期载入的年通胀率，每项从该日期起生效，按日期升序
+    pub series: Vec<Cpi>,
+}
+
+impl Default for Inflation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: Decimal::new(200, 2),
+            series: vec![],
+        }
+    }
+}
+
+impl Inflation {
+    // 某一天适用的年通胀率(%)
+    pub fn rate_on(&self, date: u32) -> Decimal {
+        self.series
+            .iter()
+            .rev()
+            .find(|cpi| cpi.date <= date)
+            .map(|cpi| cpi.rate)
+            .unwrap_or(self.rate)
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Cpi {
+    pub date: u32,
+    pub rate: Decimal,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Portfolio {
+    // 存款保险每家银行的保障上限（本金加利息）
+    pub limit: Decimal,
+    pub holdings: Vec<Holding>,
+}
+
+impl Default for Portfolio {
+    fn default() -> Self {
+        Self {
+            limit: Decimal::new(500000, 0),
+            holdings: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Holding {
+    pub bank: String,
+    pub principal: Decimal,
+    pub save_date: u32,
+    pub product: Product,
+}
+
+impl Holding {
+    pub fn order(&self, draw_date: u32) -> Order {
+        Order {
+            mode: OrderMode::LumpSum,
+            principal: self.principal,
+            recurring: Recurring::default(),
+            save_date: self.save_date,
+            draw_date,
+            days: 0,
+            withdrawal: None,
+        }
+    }
+}
+
+impl Order {
+    // 零存整取时为每期金额，否则为本金
+    pub fn amount(&self) -> Decimal {
+        match self.mode {
+            OrderMode::LumpSum => self.principal,
+            OrderMode::Recurring => self.recurring.amount,
+        }
+    }
+
+    pub fn set_amount(&mut self, amount: Decimal) {
+        match self.mode {
+            OrderMode::LumpSum => self.principal = amount,
+            OrderMode::Recurring => self.recurring.amount = amount,
+        }
+    }
+
+    pub fn amount_label(&self) -> &'static str {
+        match self.mode {
+            OrderMode::LumpSum => tr!("本金"),
+            OrderMode::Recurring => tr!("每期金额"),
+        }
+    }
+}
+
+// 大额存单：到期一次还本付息，可在到期前转让
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Cd {
+    pub face: Decimal,
+    // 票面利率(%)
+    pub rate: Decimal,
+    pub issue_date: u32,
+    pub maturity: u32,
+    pub transfer_date: u32,
+    // 买方要求的年化收益率(%)
+    pub buyer_yield: Decimal,
+}
+
+impl Default for Cd {
+    fn default() -> Self {
+        let order = Order::default();
+        Self {
+            face: Decimal::new(200000, 0),
+            rate: Decimal::new(310, 2),
+            issue_date: order.save_date,
+            maturity: order.draw_date,
+            // 起息日当天不能转让，默认取存期中点
+            transfer_date: calculator::midpoint(order.save_date, order.draw_date)
+                .unwrap_or(order.draw_date),
+            buyer_yield: Decimal::new(300, 2),
         }
     }
 }
 
+// 邦豆折算成人民币的价值
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct BeanValue {
+    // 1邦豆可兑换的金额(元)
+    pub ratio: Decimal,
+    // 兑换时的折价(%)
+    pub discount: Decimal,
+}
+
+impl Default for BeanValue {
+    fn default() -> Self {
+        Self {
+            ratio: Decimal::ONE,
+            discount: Decimal::ZERO,
+        }
+    }
+}
+
+impl BeanValue {
+    // 利息加折算后的邦豆，2位小数之后全部舍弃, 溢出归0
+    pub fn total(&self, interest: Decimal, bean_int: Decimal) -> Decimal {
+        (Decimal::ONE_HUNDRED - self.discount)
+            .checked_div(Decimal::ONE_HUNDRED)
+            .and_then(|d| d.checked_mul(self.ratio))
+            .and_then(|d| d.checked_mul(bean_int))
+            .and_then(|d| d.checked_add(interest))
+            .map(|d| d.round_dp_with_strategy(2, ToZero))
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Order {
+    #[serde(default)]
+    pub mode: OrderMode,
     pub principal: Decimal,
+    // 零存整取的每期金额、频率与期数
+    #[serde(default)]
+    pub recurring: Recurring,
     pub save_date: u32,
     pub draw_date: u32,
     pub days: i32,
+    // 存期内的一次部分提前支取
+    #[serde(default)]
+    pub withdrawal: Option<Withdrawal>,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Withdrawal {
+    pub date: u32,
+    pub amount: Decimal,
 }
 
 impl Default for Order {
@@ -46,30 +242,140 @@ impl Default for Order {
             .to_offset(UtcOffset::from_hms(8, 0, 0).unwrap())
             .date();
         Self {
+            mode: OrderMode::default(),
             principal: Decimal::new(0, 2),
+            recurring: Recurring::default(),
             save_date: now.year() as u32 * 10000 + now.month() as u32 * 100 + now.day() as u32,
             draw_date: (now.year() + 1) as u32 * 10000
                 + now.month() as u32 * 100
                 + now.day() as u32,
             days: now.replace_year(now.year() + 1).unwrap().to_julian_day() - now.to_julian_day(),
+            withdrawal: None,
+        }
+    }
+}
+
+// 一次存入本金，或按期存入（零存整取）
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum OrderMode {
+    LumpSum,
+    Recurring,
+}
+
+impl From<usize> for OrderMode {
+    fn from(i: usize) -> Self {
+        match i {
+            1 => OrderMode::Recurring,
+            _ => OrderMode::LumpSum,
         }
     }
 }
 
+impl Display for OrderMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderMode::LumpSum => write!(f, "{}", tr!("整存")),
+            OrderMode::Recurring => write!(f, "{}", tr!("零存整取")),
+        }
+    }
+}
+
+impl Default for OrderMode {
+    fn default() -> Self {
+        OrderMode::LumpSum
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(default)]
+pub struct Recurring {
+    pub amount: Decimal,
+    pub frequency: Frequency,
+    pub count: u16,
+}
+
+impl Default for Recurring {
+    fn default() -> Self {
+        Self {
+            amount: Decimal::new(0, 2),
+            frequency: Frequency::M,
+            count: 12,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Frequency {
+    M,
+    Q,
+}
+
+impl Frequency {
+    pub fn months(&self) -> i32 {
+        match self {
+            Frequency::M => 1,
+            Frequency::Q => 3,
+        }
+    }
+}
+
+impl From<usize> for Frequency {
+    fn from(i: usize) -> Self {
+        match i {
+            1 => Frequency::Q,
+            _ => Frequency::M,
+        }
+    }
+}
+
+impl Display for Frequency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Frequency::M => write!(f, "{}", tr!("每月")),
+            Frequency::Q => write!(f, "{}", tr!("每季")),
+        }
+    }
+}
+
+// 存本取息：存期内按频率把利息付到活期账户
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+#[serde(default)]
+pub struct Payout {
+    // None 表示到期一次付息
+    pub frequency: Option<Frequency>,
+    // 付出的利息是否按活期利率继续计息
+    pub earn_demand: bool,
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize, Default)]
 pub struct Product {
-    pub term: u8,
+    #[serde(default)]
+    pub kind: ProductKind,
+    // 旧版本为 u8，反序列化时可直接放宽
+    pub term: u16,
     pub term_type: TermType,
+    // 固定到期产品的到期日与认购期
+    #[serde(default)]
+    pub fixed: FixedTerm,
+    #[serde(default)]
+    pub notice: Notice,
     pub int_rate: Decimal,
     pub bean_rate: Decimal,
+    #[serde(default)]
+    pub bean_rule: BeanRule,
     pub renew_type: RenewType,
+    #[serde(default)]
+    pub payout: Payout,
     pub interest: Decimal,
     pub bean_int: Decimal,
+    // 是否在图表中显示
+    #[serde(default)]
+    pub selected: bool,
 }
 
 impl Product {
     fn new(
-        term: u8,
+        term: u16,
         term_type: TermType,
         int_rate: i64,
         bean_rate: i64,
@@ -84,20 +390,161 @@ impl Product {
             ..Default::default()
         }
     }
+
+    pub fn term_label(&self) -> String {
+        match self.kind {
+            ProductKind::Term => i18n::join(self.term, self.term_type),
+            ProductKind::Fixed => tr!("{}到期", self.fixed.maturity),
+            ProductKind::Notice => self.notice.period.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        i18n::join(self.term_label(), self.renew_type)
+    }
 }
 
+// 按存期计算到期日，或不论何时买入都在固定日期到期，或按通知存款实际天数计息
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum ProductKind {
+    Term,
+    Fixed,
+    Notice,
+}
+
+impl ProductKind {
+    pub const COUNT: usize = 3;
+}
+
+impl From<usize> for ProductKind {
+    fn from(i: usize) -> Self {
+        match i {
+            1 => ProductKind::Fixed,
+            2 => ProductKind::Notice,
+            _ => ProductKind::Term,
+        }
+    }
+}
+
+impl Display for ProductKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProductKind::Term => write!(f, "{}", tr!("存期")),
+            ProductKind::Fixed => write!(f, "{}", tr!("到期日")),
+            ProductKind::Notice => write!(f, "{}", tr!("通知")),
+        }
+    }
+}
+
+impl Default for ProductKind {
+    fn default() -> Self {
+        ProductKind::Term
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(default)]
+pub struct Notice {
+    pub period: NoticePeriod,
+    // 最低起存金额
+    pub min_balance: Decimal,
+}
+
+impl Default for Notice {
+    fn default() -> Self {
+        Self {
+            period: NoticePeriod::D7,
+            min_balance: Decimal::new(50000, 0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum NoticePeriod {
+    D1,
+    D7,
+}
+
+impl NoticePeriod {
+    pub fn days(&self) -> i32 {
+        match self {
+            NoticePeriod::D1 => 1,
+            NoticePeriod::D7 => 7,
+        }
+    }
+}
+
+impl From<usize> for NoticePeriod {
+    fn from(i: usize) -> Self {
+        match i {
+            0 => NoticePeriod::D1,
+            _ => NoticePeriod::D7,
+        }
+    }
+}
+
+impl Display for NoticePeriod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoticePeriod::D1 => write!(f, "{}", tr!("一天通知")),
+            NoticePeriod::D7 => write!(f, "{}", tr!("七天通知")),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+#[serde(default)]
+pub struct FixedTerm {
+    pub maturity: u32,
+    // 认购期起止日期，0 表示不限
+    pub open_from: u32,
+    pub open_until: u32,
+}
+
+impl FixedTerm {
+    pub fn is_open(&self, save_date: u32) -> bool {
+        (self.open_from == 0 || save_date >= self.open_from)
+            && (self.open_until == 0 || save_date <= self.open_until)
+    }
+}
+
+// 邦豆活动规则，利率仍为 Product::bean_rate
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+#[serde(default)]
+pub struct BeanRule {
+    // 每户邦豆上限，0 表示不限
+    pub cap: Decimal,
+    // 持有不足该天数不发放邦豆（含首存奖励）
+    pub min_days: u32,
+    // 新存入一次性奖励
+    pub bonus: Decimal,
+    // 活动截止日期，之后不再计邦豆，0 或无效日期表示长期有效
+    pub valid_until: u32,
+}
+
+// 按名称序列化，新增的类型不影响已保存的配置
 #[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub enum TermType {
     D,
+    W,
     M,
+    Q,
+    H,
     Y,
 }
 
+impl TermType {
+    pub const COUNT: usize = 6;
+}
+
 impl From<usize> for TermType {
     fn from(i: usize) -> Self {
         match i {
-            1 => TermType::M,
-            2 => TermType::Y,
+            1 => TermType::W,
+            2 => TermType::M,
+            3 => TermType::Q,
+            4 => TermType::H,
+            5 => TermType::Y,
             _ => TermType::D,
         }
     }
@@ -106,9 +553,12 @@ impl From<usize> for TermType {
 impl Display for TermType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            TermType::D => write!(f, "天"),
-            TermType::M => write!(f, "月"),
-            TermType::Y => write!(f, "年"),
+            TermType::D => write!(f, "{}", tr!("天")),
+            TermType::W => write!(f, "{}", tr!("周")),
+            TermType::M => write!(f, "{}", tr!("月")),
+            TermType::Q => write!(f, "{}", tr!("季")),
+            TermType::H => write!(f, "{}", tr!("半年")),
+            TermType::Y => write!(f, "{}", tr!("年")),
         }
     }
 }
@@ -139,9 +589,9 @@ impl From<usize> for RenewType {
 impl Display for RenewType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            RenewType::N => write!(f, "不续存"),
-            RenewType::P => write!(f, "本金续存"),
-            RenewType::I => write!(f, "本息续存"),
+            RenewType::N => write!(f, "{}", tr!("不续存")),
+            RenewType::P => write!(f, "{}", tr!("本金续存")),
+            RenewType::I => write!(f, "{}", tr!("本息续存")),
         }
     }
 }
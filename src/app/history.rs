@@ -0,0 +1,82 @@
+use crate::app::config::Config;
+
+const HISTORY_MAX: usize = 100;
+
+// 被修改的字段，同一输入框获得焦点期间的连续输入合并为一步撤销
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Field {
+    Principal,
+    SaveDate,
+    DrawDate,
+    Recurring,
+    Withdrawal,
+    Term(usize),
+    Fixed(usize),
+    Notice(usize),
+    IntRate(usize),
+    BeanRate(usize),
+    BeanRule(usize),
+    Scenario(usize),
+    BeanValue,
+    Cd,
+    Inflation,
+    Portfolio,
+    Holding(usize),
+    Other,
+}
+
+impl Field {
+    fn coalesce(&self) -> bool {
+        !matches!(self, Field::Other)
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Config>,
+    redo: Vec<Config>,
+    last: Option<Field>,
+}
+
+impl History {
+    // 修改前调用，记录修改前的配置
+    pub fn record(&mut self, cfg: &Config, field: Field) {
+        if field.coalesce() && self.last == Some(field) {
+            return;
+        }
+
+        self.undo.push(cfg.clone());
+        if self.undo.len() > HISTORY_MAX {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.last = Some(field);
+    }
+
+    // 离开输入框后再次编辑同一字段时另起一步
+    pub fn break_merge(&mut self) {
+        self.last = None;
+    }
+
+    pub fn undo(&mut self, cfg: &Config) -> Option<Config> {
+        let prev = self.undo.pop()?;
+        self.redo.push(cfg.clone());
+        self.last = None;
+        Some(prev)
+    }
+
+    pub fn redo(&mut self, cfg: &Config) -> Option<Config> {
+        let next = self.redo.pop()?;
+        self.undo.push(cfg.clone());
+        self.last = None;
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
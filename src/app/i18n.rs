@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+// 界面语言。以简体中文原文作为词条的键，其他语言在 resource/i18n 下的词表中查找，
+// 找不到译文时显示原文
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Lang {
+    ZhCn,
+    EnUs,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::ZhCn, Lang::EnUs];
+
+    // 语言名称总是用该语言本身显示
+    pub fn label(&self) -> &'static str {
+        match self {
+            Lang::ZhCn => "简体中文",
+            Lang::EnUs => "English",
+        }
+    }
+
+    // 命令行没有界面设置，按系统语言环境选择
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_default();
+        if locale.starts_with("en") {
+            Lang::EnUs
+        } else {
+            Lang::ZhCn
+        }
+    }
+
+    fn catalog(&self) -> Option<&'static HashMap<String, String>> {
+        static EN_US: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Lang::ZhCn => None,
+            Lang::EnUs => Some(EN_US.get_or_init(|| {
+                toml::from_str(include_str!("../../resource/i18n/en-US.toml")).unwrap_or_default()
+            })),
+        }
+    }
+}
+
+impl Default for Lang {
+    fn default() -> Self {
+        Lang::ZhCn
+    }
+}
+
+// Display 实现也要按语言输出，放在全局而不是 App 中
+static LANG: AtomicU8 = AtomicU8::new(Lang::ZhCn as u8);
+
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::EnUs,
+        _ => Lang::ZhCn,
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn text(key: &'static str) -> &'static str {
+    lang()
+        .catalog()
+        .and_then(|catalog| catalog.get(key))
+        .map(String::as_str)
+        .unwrap_or(key)
+}
+
+// 两个词连成一个名称，中文不加空格
+pub fn join(a: impl Display, b: impl Display) -> String {
+    match lang() {
+        Lang::ZhCn => format!("{a}{b}"),
+        Lang::EnUs => format!("{a} {b}"),
+    }
+}
+
+// 按顺序把参数填入译文中的 {}，参数需事先格式化好
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(i) = rest.find("{}") {
+        out.push_str(&rest[..i]);
+        if let Some(arg) = args.next() {
+            let _ = write!(out, "{arg}");
+        }
+        rest = &rest[i + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// tr!("原文") 返回 &'static str，tr!("原文{}", 参数) 返回 String
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::app::i18n::text($key)
+    };
+    ($key:literal, $($arg:expr),+ $(,)?) => {
+        $crate::app::i18n::fill(
+            $crate::app::i18n::text($key),
+            &[$(&($arg) as &dyn std::fmt::Display),+],
+        )
+    };
+}
+
+pub(crate) use tr;
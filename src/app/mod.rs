@@ -1,35 +1,256 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
-use async_channel::{Receiver, Sender};
-use eframe::egui::{Align, Color32, ComboBox, RichText, TextEdit, Widget};
+use eframe::egui::{Align, Color32, ComboBox, Key, RichText, TextEdit, Widget};
 use eframe::{egui, Frame, Storage};
 use egui_extras::{Size, TableBuilder};
-use futures::executor::ThreadPoolBuilder;
-use rayon::prelude::*;
 use rust_decimal::Decimal;
 use rust_decimal::RoundingStrategy::ToZero;
 
-use config::{Config, Product, RenewType, TermType};
+use config::{
+    Config, Frequency, NoticePeriod, OrderMode, Product, ProductKind, RenewType, TermType,
+    Withdrawal,
+};
 
+use crate::app::cache::{Cache, CacheSettings, PersistedCache};
 use crate::app::calculator::Req;
+use crate::app::history::{Field, History};
+use crate::app::i18n::{tr, Lang};
+use crate::app::sensitivity::Sensitivity;
+use crate::app::sweep::Sweep;
+use crate::app::worker::{Batches, Lane, Worker};
 
-mod calculator;
-mod config;
+mod cache;
+pub(crate) mod calculator;
+mod chart;
+mod clipboard;
+mod compare;
+pub(crate) mod config;
+mod history;
+pub(crate) mod i18n;
+mod portfolio;
+pub(crate) mod report;
+pub(crate) mod scenario;
+mod sensitivity;
+mod sweep;
+mod transfer;
+mod worker;
+mod xlsx;
+
+// 界面和 PDF 报告共用的中文字体
+#[cfg(not(target_arch = "wasm32"))]
+const FONT: &[u8] = include_bytes!("../../resource/simkai.ttf");
+#[cfg(target_arch = "wasm32")]
+const FONT_URL: &str = "simkai.ttf";
+const RECENT_KEY: &str = "recent_files";
+const CACHE_SETTINGS_KEY: &str = "cache_settings";
+const CACHE_KEY: &str = "result_cache";
+const LANG_KEY: &str = "language";
 
 pub struct App {
     cfg: Config,
     warn: Result<()>,
-    // worker: ThreadPool,
-    req_s: Sender<Req>,
-    res_r: Receiver<HashMap<Req, (Decimal, Decimal)>>,
-    cache: HashMap<Req, (Decimal, Decimal)>,
+    worker: Worker,
+    batches: Batches,
+    cache: Cache,
+    cache_settings: CacheSettings,
+    cache_open: bool,
+    transfer_open: bool,
+    transfer_error: Option<anyhow::Error>,
+    portfolio_open: bool,
+    portfolio_error: Option<anyhow::Error>,
+    exposures: Option<portfolio::Exposures>,
+    file: Option<PathBuf>,
+    recent: Vec<PathBuf>,
+    dirty: bool,
+    // 有未保存的修改时，等待确认后再打开的文件
+    pending_open: Option<PathBuf>,
+    history: History,
+    focus: Option<egui::Id>,
+    compare_open: bool,
+    compare_error: Option<anyhow::Error>,
+    chart_open: bool,
+    chart_series: HashMap<Req, chart::Series>,
+    sweep_open: bool,
+    sweep_from: String,
+    sweep_to: String,
+    sweep: Option<Sweep>,
+    // 临界点窗口自己的输入错误，不影响产品列表
+    sweep_error: Option<anyhow::Error>,
+    sensitivity_open: bool,
+    sensitivity_product: usize,
+    sensitivity_principals: String,
+    sensitivity_columns: String,
+    sensitivity_by_days: bool,
+    sensitivity: Option<Sensitivity>,
+    sensitivity_error: Option<anyhow::Error>,
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
         self.refresh_cache();
 
+        // 焦点变化时结束撤销合并；输入框有焦点时由它自己处理撤销快捷键
+        let focus = ctx.memory().focus();
+        if focus != self.focus {
+            self.focus = focus;
+            self.history.break_merge();
+        }
+        let (undo, redo) = if focus.is_some() {
+            (false, false)
+        } else {
+            let input = ctx.input();
+            let command = input.modifiers.command;
+            let shift = input.modifiers.shift;
+            (
+                command && !shift && input.key_pressed(Key::Z),
+                command && (input.key_pressed(Key::Y) || shift && input.key_pressed(Key::Z)),
+            )
+        };
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+
+        egui::TopBottomPanel::top("menu").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                if scenario::HAS_FILES {
+                    ui.menu_button(tr!("文件"), |ui| {
+                        if ui.button(tr!("打开…")).clicked() {
+                            ui.close_menu();
+                            if let Some(path) = scenario::pick_open() {
+                                self.request_open(path);
+                            }
+                        }
+                        if ui.button(tr!("保存")).clicked() {
+                            ui.close_menu();
+                            self.save_file();
+                        }
+                        if ui.button(tr!("另存为…")).clicked() {
+                            ui.close_menu();
+                            self.save_file_as();
+                        }
+                        ui.separator();
+                        if ui.button(tr!("导出HTML报告…")).clicked() {
+                            ui.close_menu();
+                            self.export_report("HTML", "html");
+                        }
+                        if ui.button(tr!("导出PDF报告…")).clicked() {
+                            ui.close_menu();
+                            self.export_report("PDF", "pdf");
+                        }
+                        if ui.button(tr!("导出Excel…")).clicked() {
+                            ui.close_menu();
+                            self.export_xlsx();
+                        }
+                        ui.separator();
+                        ui.menu_button(tr!("最近打开"), |ui| {
+                            if self.recent.is_empty() {
+                                ui.label(tr!("无"));
+                            }
+                            let mut opened = None;
+                            for path in &self.recent {
+                                if ui.button(path.display().to_string()).clicked() {
+                                    opened = Some(path.clone());
+                                }
+                            }
+                            if let Some(path) = opened {
+                                ui.close_menu();
+                                self.request_open(path);
+                            }
+                        });
+                    });
+                }
+
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new(tr!("撤销")))
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new(tr!("重做")))
+                    .clicked()
+                {
+                    self.redo();
+                }
+
+                ui.menu_button(tr!("复制表格"), |ui| {
+                    for format in clipboard::Format::ALL {
+                        if ui.button(format.label()).clicked() {
+                            ui.close_menu();
+                            ui.output().copied_text =
+                                clipboard::table(&self.cfg.products, format);
+                        }
+                    }
+                });
+
+                if ui.selectable_label(self.compare_open, tr!("方案对比")).clicked() {
+                    self.compare_open = !self.compare_open;
+                }
+                if ui.selectable_label(self.chart_open, tr!("收益走势")).clicked() {
+                    self.chart_open = !self.chart_open;
+                }
+                if ui.selectable_label(self.sweep_open, tr!("收益临界点")).clicked() {
+                    self.sweep_open = !self.sweep_open;
+                }
+                if ui
+                    .selectable_label(self.sensitivity_open, tr!("收益测算表"))
+                    .clicked()
+                {
+                    self.sensitivity_open = !self.sensitivity_open;
+                }
+                if ui.selectable_label(self.transfer_open, tr!("存单转让")).clicked() {
+                    self.transfer_open = !self.transfer_open;
+                }
+                if ui.selectable_label(self.portfolio_open, tr!("存款保险")).clicked() {
+                    self.portfolio_open = !self.portfolio_open;
+                }
+                if ui.selectable_label(self.cache_open, tr!("缓存")).clicked() {
+                    self.cache_open = !self.cache_open;
+                }
+
+                let name = self
+                    .file
+                    .as_ref()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| tr!("未命名").to_owned());
+                let dirty = if self.dirty { " *" } else { "" };
+                if scenario::HAS_FILES {
+                    ui.label(format!("{name}{dirty}"));
+                }
+            });
+        });
+
+        if self.compare_open {
+            self.show_compare(ctx);
+        }
+        if self.chart_open {
+            self.show_chart(ctx);
+        }
+        if self.sweep_open {
+            self.show_sweep(ctx);
+        }
+        if self.sensitivity_open {
+            self.show_sensitivity(ctx);
+        }
+        if self.transfer_open {
+            self.show_transfer(ctx);
+        }
+        if self.portfolio_open {
+            self.show_portfolio(ctx);
+        }
+        if self.cache_open {
+            self.show_cache(ctx);
+        }
+        if self.pending_open.is_some() {
+            self.show_unsaved(ctx);
+        }
+
         if let Err(e) = &self.warn {
             egui::TopBottomPanel::top("my_panel").show(ctx, |ui| {
                 let warn = RichText::from(e.to_string()).color(Color32::RED);
@@ -40,6 +261,10 @@ impl eframe::App for App {
         egui::CentralPanel::default().show(ctx, |ui| {
             let text_height = egui::TextStyle::Body.resolve(ui.style()).size * 2.0;
 
+            ui.horizontal(|ui| {
+                self.mode_ui(ui);
+            });
+
             TableBuilder::new(ui)
                 .cell_layout(egui::Layout::left_to_right(Align::Center))
                 .column(Size::remainder())
@@ -47,16 +272,25 @@ impl eframe::App for App {
                 .column(Size::remainder())
                 .column(Size::remainder())
                 .header(text_height, |mut header| {
-                    header.col(|ui| {
-                        ui.heading("本金");
-                        let mut principal = format!("{:.2}", self.cfg.order.principal);
-                        if ui.text_edit_singleline(&mut principal).changed() {
-                            self.principal_changed(&*principal);
-                        };
+                    header.col(|ui| match self.cfg.order.mode {
+                        OrderMode::LumpSum => {
+                            ui.heading(tr!("本金"));
+                            let mut principal = format!("{:.2}", self.cfg.order.principal);
+                            if ui.text_edit_singleline(&mut principal).changed() {
+                                self.principal_changed(&*principal);
+                            };
+                        }
+                        OrderMode::Recurring => {
+                            ui.heading(tr!("每期金额"));
+                            let mut amount = format!("{:.2}", self.cfg.order.recurring.amount);
+                            if ui.text_edit_singleline(&mut amount).changed() {
+                                self.amount_changed(&*amount);
+                            };
+                        }
                     });
 
                     header.col(|ui| {
-                        ui.heading("购买日期：");
+                        ui.heading(tr!("购买日期："));
                         let mut save_date = self.cfg.order.save_date.to_string();
                         if ui.text_edit_singleline(&mut save_date).changed() {
                             save_date.truncate(8);
@@ -65,7 +299,7 @@ impl eframe::App for App {
                     });
 
                     header.col(|ui| {
-                        ui.heading("支取日期：");
+                        ui.heading(tr!("支取日期："));
                         let mut draw_date = self.cfg.order.draw_date.to_string();
                         if ui.text_edit_singleline(&mut draw_date).changed() {
                             draw_date.truncate(8);
@@ -74,43 +308,81 @@ impl eframe::App for App {
                     });
 
                     header.col(|ui| {
-                        ui.heading(format!("天数：{}", self.cfg.order.days));
+                        ui.heading(tr!("天数：{}", self.cfg.order.days));
                     });
                 });
 
             ui.separator();
 
-            TableBuilder::new(ui)
+            // 总收益最高的产品高亮显示
+            let value = self.cfg.bean_value;
+            let best = self
+                .cfg
+                .products
+                .iter()
+                .map(|p| value.total(p.interest, p.bean_int))
+                .max()
+                .filter(|best| *best > Decimal::ZERO);
+
+            // 开启通胀调整时增加实际收益两列
+            let order = self.cfg.order;
+            let inflation = self.cfg.inflation.clone();
+            let mut table = TableBuilder::new(ui)
                 .striped(true)
                 .cell_layout(egui::Layout::left_to_right(Align::Center))
-                .column(Size::initial(80.0))
+                .column(Size::initial(20.0))
+                .column(Size::initial(180.0))
                 .column(Size::remainder())
                 .column(Size::remainder())
-                .column(Size::initial(90.0))
+                .column(Size::initial(130.0))
                 .column(Size::remainder())
                 .column(Size::remainder())
-                .column(Size::initial(40.0))
+                .column(Size::remainder());
+            if inflation.enabled {
+                table = table.column(Size::remainder()).column(Size::remainder());
+            }
+            table
+                .column(Size::initial(90.0))
                 .header(text_height, |mut header| {
+                    header.col(|_ui| {});
                     header.col(|ui| {
-                        ui.heading("存期");
+                        ui.heading(tr!("存期"));
                     });
                     header.col(|ui| {
-                        ui.heading("利率(%)");
+                        ui.heading(tr!("利率(%)"));
                     });
                     header.col(|ui| {
-                        ui.heading("邦豆利率(%)");
+                        ui.heading(tr!("邦豆利率(%)"));
                     });
                     header.col(|ui| {
-                        ui.heading("续存类型");
+                        ui.heading(tr!("续存类型"));
                     });
                     header.col(|ui| {
-                        ui.heading("利息");
+                        ui.heading(tr!("利息"));
                     });
                     header.col(|ui| {
-                        ui.heading("邦豆利息");
+                        ui.heading(tr!("邦豆利息"));
+                    });
+                    header.col(|ui| {
+                        if ui
+                            .button(RichText::new(tr!("总收益⬇")).heading())
+                            .on_hover_text(tr!("按总收益从高到低排序"))
+                            .clicked()
+                        {
+                            self.sort_by_total();
+                        }
                     });
+                    if inflation.enabled {
+                        header.col(|ui| {
+                            ui.heading(tr!("实际收益"));
+                        });
+                        header.col(|ui| {
+                            ui.heading(tr!("实际年化(%)"));
+                        });
+                    }
                     header.col(|ui| {
-                        if ui.button("添加").clicked() {
+                        if ui.button(tr!("添加")).clicked() {
+                            self.edit(Field::Other);
                             self.cfg.products.push(Product::default());
                         }
                     });
@@ -123,28 +395,17 @@ impl eframe::App for App {
                             if self.cfg.products.get_mut(row_index).is_none() {
                                 return;
                             }
+                            row.col(|ui| {
+                                // 勾选状态随方案保存，也要记入撤销历史
+                                let mut selected = self.cfg.products[row_index].selected;
+                                if ui.checkbox(&mut selected, "").changed() {
+                                    self.edit(Field::Other);
+                                    self.cfg.products[row_index].selected = selected;
+                                }
+                            });
                             row.col(|ui| {
                                 ui.horizontal(|ui| {
-                                    let mut term = self.cfg.products[row_index].term.to_string();
-                                    if TextEdit::singleline(&mut term)
-                                        .desired_width(20.0)
-                                        .ui(ui)
-                                        .changed()
-                                    {
-                                        self.term_changed(&*term, row_index);
-                                    }
-
-                                    let mut term_type =
-                                        self.cfg.products[row_index].term_type as usize;
-                                    if ComboBox::from_id_source(format!("存期类型{}", row_index))
-                                        .width(20.0)
-                                        .show_index(ui, &mut term_type, 3, |i| {
-                                            TermType::from(i).to_string()
-                                        })
-                                        .changed()
-                                    {
-                                        self.term_type_changed(term_type, row_index);
-                                    };
+                                    self.term_ui(ui, row_index);
                                 });
                             });
                             row.col(|ui| {
@@ -157,9 +418,16 @@ impl eframe::App for App {
                             row.col(|ui| {
                                 let mut bean_rate =
                                     format!("{:.2}", self.cfg.products[row_index].bean_rate);
-                                if ui.text_edit_singleline(&mut bean_rate).changed() {
+                                if TextEdit::singleline(&mut bean_rate)
+                                    .desired_width(40.0)
+                                    .ui(ui)
+                                    .changed()
+                                {
                                     self.bean_rate_changed(&*bean_rate, row_index);
                                 };
+                                ui.menu_button(tr!("规则"), |ui| {
+                                    self.bean_rule_ui(ui, row_index);
+                                });
                             });
                             row.col(|ui| {
                                 let mut renew_type =
@@ -173,15 +441,62 @@ impl eframe::App for App {
                                 {
                                     self.renew_type_changed(renew_type, row_index);
                                 };
+                                ui.menu_button(tr!("付息"), |ui| {
+                                    self.payout_ui(ui, row_index);
+                                });
                             });
                             row.col(|ui| {
-                                ui.label(format!("{:.2}", self.cfg.products[row_index].interest));
+                                let p = &self.cfg.products[row_index];
+                                ui.label(format!("{:.2}", p.interest));
+                                // 通知存款不满足起存金额或通知期时只在该行提示
+                                if let Err(e) = calculator::check_notice(&order, p) {
+                                    ui.label(RichText::new("⚠").color(Color32::RED))
+                                        .on_hover_text(e.to_string());
+                                }
                             });
                             row.col(|ui| {
                                 ui.label(format!("{:.2}", self.cfg.products[row_index].bean_int));
                             });
                             row.col(|ui| {
-                                if ui.button("删除").clicked() {
+                                let p = &self.cfg.products[row_index];
+                                let total = value.total(p.interest, p.bean_int);
+                                let text = RichText::new(format!("{:.2}", total));
+                                if Some(total) == best {
+                                    ui.label(text.strong().color(Color32::GREEN));
+                                } else {
+                                    ui.label(text);
+                                }
+                            });
+                            if inflation.enabled {
+                                let p = &self.cfg.products[row_index];
+                                let total = value.total(p.interest, p.bean_int);
+                                let real = calculator::real_return(&order, &inflation, total);
+                                row.col(|ui| {
+                                    ui.label(match &real {
+                                        Some(real) => format!("{:.2}", real.interest),
+                                        None => "-".to_owned(),
+                                    });
+                                });
+                                row.col(|ui| {
+                                    ui.label(match &real {
+                                        Some(real) => format!("{:.4}", real.yield_rate),
+                                        None => "-".to_owned(),
+                                    });
+                                });
+                            }
+                            row.col(|ui| {
+                                ui.menu_button(tr!("复制"), |ui| {
+                                    for format in clipboard::Format::ALL {
+                                        if ui.button(format.label()).clicked() {
+                                            ui.close_menu();
+                                            let product = &self.cfg.products[row_index];
+                                            ui.output().copied_text =
+                                                clipboard::table([product], format);
+                                        }
+                                    }
+                                });
+                                if ui.button(tr!("删除")).clicked() {
+                                    self.edit(Field::Other);
                                     self.cfg.products.remove(row_index);
                                 }
                             });
@@ -191,12 +506,109 @@ impl eframe::App for App {
         });
 
         egui::TopBottomPanel::bottom("bottom").show(ctx, |ui| {
-            egui::widgets::global_dark_light_mode_switch(ui);
+            ui.horizontal(|ui| {
+                egui::widgets::global_dark_light_mode_switch(ui);
+                let mut lang = i18n::lang();
+                ComboBox::from_id_source("lang")
+                    .selected_text(lang.label())
+                    .show_ui(ui, |ui| {
+                        for l in Lang::ALL {
+                            ui.selectable_value(&mut lang, l, l.label());
+                        }
+                    });
+                if lang != i18n::lang() {
+                    i18n::set_lang(lang);
+                    // 已生成的提示按新语言重新生成
+                    self.sweep_error = None;
+                    self.sensitivity_error = None;
+                    self.portfolio_error = None;
+                    self.transfer_error = None;
+                    self.compare_error = None;
+                    self.calc();
+                }
+
+                ui.separator();
+                ui.label(tr!("1邦豆="));
+                let mut ratio = self.cfg.bean_value.ratio.to_string();
+                if TextEdit::singleline(&mut ratio)
+                    .desired_width(40.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.bean_ratio_changed(&*ratio);
+                }
+                ui.label(tr!("元，折价(%)"));
+                let mut discount = format!("{:.2}", self.cfg.bean_value.discount);
+                if TextEdit::singleline(&mut discount)
+                    .desired_width(40.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.bean_discount_changed(&*discount);
+                }
+
+                ui.separator();
+                let mut enabled = self.cfg.inflation.enabled;
+                if ui.checkbox(&mut enabled, tr!("通胀调整")).changed() {
+                    self.edit(Field::Other);
+                    self.cfg.inflation.enabled = enabled;
+                }
+                if enabled {
+                    ui.label(tr!("年通胀率(%)"));
+                    let mut rate = format!("{:.2}", self.cfg.inflation.rate);
+                    if TextEdit::singleline(&mut rate)
+                        .desired_width(40.0)
+                        .ui(ui)
+                        .changed()
+                    {
+                        self.inflation_rate_changed(&*rate);
+                    }
+                    if scenario::HAS_FILES
+                        && ui
+                            .button(tr!("载入CPI…"))
+                            .on_hover_text(tr!("每行为“日期,年通胀率(%)”，未覆盖的日期使用上面的年通胀率"))
+                            .clicked()
+                    {
+                        self.load_cpi();
+                    }
+                    if !self.cfg.inflation.series.is_empty() {
+                        ui.label(tr!("CPI序列{}项", self.cfg.inflation.series.len()));
+                        if ui.button(tr!("清除")).clicked() {
+                            self.edit(Field::Other);
+                            self.cfg.inflation.series.clear();
+                        }
+                    }
+                }
+
+                if let Some((done, total)) = self.worker.progress() {
+                    ui.separator();
+                    ui.spinner();
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / total as f32)
+                            .desired_width(160.0)
+                            .text(format!("{done}/{total}")),
+                    );
+                    if ui.button(tr!("取消")).clicked() {
+                        self.cancel_batches();
+                    }
+                }
+            });
         });
     }
 
     fn save(&mut self, storage: &mut dyn Storage) {
         eframe::set_value(storage, eframe::APP_KEY, &self.cfg);
+        eframe::set_value(storage, RECENT_KEY, &self.recent);
+        eframe::set_value(storage, CACHE_SETTINGS_KEY, &self.cache_settings);
+        eframe::set_value(storage, LANG_KEY, &i18n::lang());
+        // 缓存条目很多，有变化时才写入
+        if self.cache.take_changed() {
+            if self.cache_settings.persist {
+                eframe::set_value(storage, CACHE_KEY, &self.cache.to_persisted());
+            } else {
+                storage.set_string(CACHE_KEY, String::new());
+            }
+        }
     }
 }
 
@@ -207,136 +619,432 @@ impl App {
         // egui_ctx.set_visuals(egui::Visuals::dark());
         // egui_ctx.set_debug_on_hover(true);
 
+        let lang = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Lang>(storage, LANG_KEY))
+            .unwrap_or_default();
+        i18n::set_lang(lang);
+
         let cfg = cc
             .storage
             .and_then(|storage| eframe::get_value::<Config>(storage, eframe::APP_KEY))
             .unwrap_or_default();
+        let recent = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Vec<PathBuf>>(storage, RECENT_KEY))
+            .unwrap_or_default();
+
+        let cache_settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<CacheSettings>(storage, CACHE_SETTINGS_KEY))
+            .unwrap_or_default();
 
-        let worker = ThreadPoolBuilder::new().pool_size(1).create().unwrap();
+        let mut cache = Cache::new(cache_settings.capacity);
+        if cache_settings.persist {
+            if let Some(persisted) = cc
+                .storage
+                .and_then(|storage| eframe::get_value::<PersistedCache>(storage, CACHE_KEY))
+            {
+                cache.load(persisted);
+            }
+        }
+        let sweep_from = cfg.order.save_date.to_string();
+        let sweep_to = cfg.order.draw_date.to_string();
 
-        //req 需要计算的key (本金-购买日期-支取日期-产品存期-存期类型-利率-邦豆利率-滚存类型)
-        let (req_s, req_r) = async_channel::unbounded::<Req>();
-        //res 计算结果HashMap<key,value> ()
-        let (res_s, res_r) = async_channel::unbounded::<HashMap<Req, (Decimal, Decimal)>>();
+        let mut app = Self {
+            cfg,
+            warn: Ok(()),
+            worker: Worker::new(egui_ctx),
+            batches: Batches::default(),
+            cache,
+            cache_settings,
+            cache_open: false,
+            transfer_open: false,
+            transfer_error: None,
+            portfolio_open: false,
+            portfolio_error: None,
+            exposures: None,
+            file: None,
+            recent,
+            dirty: false,
+            pending_open: None,
+            history: History::default(),
+            focus: None,
+            compare_open: false,
+            compare_error: None,
+            chart_open: false,
+            chart_series: HashMap::new(),
+            sweep_open: false,
+            sweep_from,
+            sweep_to,
+            sweep: None,
+            sweep_error: None,
+            sensitivity_open: false,
+            sensitivity_product: 0,
+            sensitivity_principals: String::new(),
+            sensitivity_columns: String::new(),
+            sensitivity_by_days: false,
+            sensitivity: None,
+            sensitivity_error: None,
+        };
+        // 保存的结果可能是旧规则算出的，启动时重新计算
+        app.calc();
+        app
+    }
 
-        worker.spawn_ok(async move {
-            while let Ok(req) = req_r.recv().await {
-                let mut reqs = HashMap::new();
-                reqs.insert(req, (Decimal::ZERO, Decimal::ZERO));
+    // 修改配置前调用，记录撤销历史并标记未保存
+    fn edit(&mut self, field: Field) {
+        self.history.record(&self.cfg, field);
+        self.dirty = true;
+    }
 
-                while let Ok(req_more) = req_r.try_recv() {
-                    reqs.insert(req_more, (Decimal::ZERO, Decimal::ZERO));
-                }
+    fn undo(&mut self) {
+        if let Some(cfg) = self.history.undo(&self.cfg) {
+            self.cfg = cfg;
+            self.dirty = true;
+            self.calc();
+        }
+    }
 
-                reqs.par_iter_mut()
-                    .for_each(|(req, res)| *res = calculator::calc(req));
+    fn redo(&mut self) {
+        if let Some(cfg) = self.history.redo(&self.cfg) {
+            self.cfg = cfg;
+            self.dirty = true;
+            self.calc();
+        }
+    }
 
-                res_s.send(reqs).await.ok();
-                egui_ctx.request_repaint();
+    // 有未保存的修改时先询问
+    fn request_open(&mut self, path: PathBuf) {
+        if self.dirty {
+            self.pending_open = Some(path);
+        } else {
+            self.open(&path);
+        }
+    }
+
+    fn show_unsaved(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr!("未保存的修改"))
+            .id(egui::Id::new("unsaved"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("当前方案有未保存的修改，是否先保存？"));
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("保存")).clicked() {
+                        // 保存失败或取消另存为时继续询问
+                        self.save_file();
+                        if !self.dirty {
+                            if let Some(path) = self.pending_open.take() {
+                                self.open(&path);
+                            }
+                        }
+                    }
+                    if ui.button(tr!("不保存")).clicked() {
+                        if let Some(path) = self.pending_open.take() {
+                            self.open(&path);
+                        }
+                    }
+                    if ui.button(tr!("取消")).clicked() {
+                        self.pending_open = None;
+                    }
+                });
+            });
+    }
+
+    fn open(&mut self, path: &Path) {
+        match scenario::load(path) {
+            Ok(cfg) => {
+                self.cfg = cfg;
+                self.file = Some(path.to_path_buf());
+                self.dirty = false;
+                self.history = History::default();
+                scenario::push_recent(&mut self.recent, path);
+                self.calc();
             }
-        });
+            Err(e) => {
+                self.recent.retain(|p| p != path);
+                self.warn = Err(e);
+            }
+        }
+    }
 
-        let mut cache = HashMap::new();
-        cfg.products.iter().for_each(|p| {
-            cache.insert(Req::new(&cfg.order, p), (p.interest, p.bean_int));
-        });
+    fn save_file(&mut self) {
+        match self.file.clone() {
+            Some(path) => self.save_to(&path),
+            None => self.save_file_as(),
+        }
+    }
 
-        Self {
-            cfg,
-            warn: Ok(()),
-            // worker,
-            req_s,
-            res_r,
-            cache,
+    fn save_file_as(&mut self) {
+        if let Some(path) = scenario::pick_save() {
+            self.save_to(&path);
+        }
+    }
+
+    fn save_to(&mut self, path: &Path) {
+        match scenario::save(path, &self.cfg) {
+            Ok(()) => {
+                self.file = Some(path.to_path_buf());
+                self.dirty = false;
+                scenario::push_recent(&mut self.recent, path);
+            }
+            Err(e) => self.warn = Err(e),
+        }
+    }
+
+    fn export_report(&mut self, filter: &str, ext: &str) {
+        if let Some(path) = scenario::pick_export(filter, ext, &tr!("存款收益报告.{}", ext)) {
+            if let Err(e) = report::export(&self.cfg, &path) {
+                self.warn = Err(e);
+            }
+        }
+    }
+
+    fn export_xlsx(&mut self) {
+        if let Some(path) = scenario::pick_export("Excel", "xlsx", tr!("存款收益.xlsx")) {
+            if let Err(e) = xlsx::export(&self.cfg, &path) {
+                self.warn = Err(e);
+            }
         }
     }
 
     fn refresh_cache(&mut self) {
-        while let Ok(res) = self.res_r.try_recv() {
-            res.iter().for_each(|(k, v)| {
-                self.cache.insert(*k, *v);
+        while let Some((lane, res)) = self.worker.try_recv() {
+            self.batches.deliver(lane, &res);
+            res.into_iter().for_each(|(k, v)| {
+                self.cache.insert(k, v);
             })
         }
 
+        let order = &self.cfg.order;
+        let batches = &self.batches;
+        let cache = &mut self.cache;
         self.cfg.products.iter_mut().for_each(|p| {
-            if let Some(res) = self.cache.get(&Req::new(&self.cfg.order, p)) {
+            let req = Req::new(order, p);
+            if let Some(res) = batches.get(Lane::Visible, &req).or_else(|| cache.get(&req)) {
                 p.interest = res.0;
                 p.bean_int = res.1;
             }
         })
     }
 
-    fn calc(&mut self, index: Option<usize>) {
-        self.warn = calculator::check_date(&mut self.cfg.order);
+    // 当前方案的所有产品，未命中缓存的交给工作线程，新的请求会取消尚未完成的旧请求
+    fn calc(&mut self) {
+        self.warn = calculator::check_date(&mut self.cfg.order)
+            .and_then(|_| calculator::check_withdrawal(&self.cfg.order));
 
         if self.warn.is_ok() {
-            let order = &self.cfg.order;
-            self.cfg
+            let reqs = self
+                .cfg
                 .products
-                .par_iter_mut()
-                .enumerate()
-                .for_each(|(i, product)| {
-                    if index.is_some() && index.ne(&Some(i)) {
-                        return;
-                    }
+                .iter()
+                .map(|product| Req::new(&self.cfg.order, product))
+                .collect();
+            self.request(Lane::Visible, reqs);
+            self.refresh_cache();
+        }
+    }
 
-                    let req = Req::new(order, product);
-                    if let Some(res) = self.cache.get(&req) {
-                        product.interest = res.0;
-                        product.bean_int = res.1;
-                    } else {
-                        self.req_s.send_blocking(req).unwrap();
-                    }
-                });
+    // 取消对比、临界点、测算表等批量计算，当前产品列表不受影响
+    fn cancel_batches(&mut self) {
+        self.worker.cancel(Lane::Compare);
+        self.worker.cancel(Lane::Sweep);
+        self.worker.cancel(Lane::Sensitivity);
+        // 对比窗口每帧都会请求，保留已取消的请求集合，输入变化前不再提交
+        self.batches.clear(Lane::Sweep);
+        self.batches.clear(Lane::Sensitivity);
+        self.sweep = None;
+        self.sensitivity = None;
+    }
+
+    // 请求集合与上次相同时直接返回；否则先取缓存中已有的结果，其余提交计算。
+    // 结果按来源保存，读取时不依赖缓存，批量大于缓存容量也不会丢失
+    fn request(&mut self, lane: Lane, reqs: Vec<Req>) {
+        let reqs: HashSet<Req> = reqs.into_iter().collect();
+        if self.batches.is_same(lane, &reqs) {
+            return;
         }
+
+        let cache = &mut self.cache;
+        let batch: HashMap<Req, Option<(Decimal, Decimal)>> = reqs
+            .into_iter()
+            .map(|req| (req, cache.lookup(&req)))
+            .collect();
+        let missing = batch
+            .iter()
+            .filter(|(_, res)| res.is_none())
+            .map(|(req, _)| *req)
+            .collect();
+        self.batches.set(lane, batch);
+        self.worker.submit(lane, missing);
     }
 
     fn principal_changed(&mut self, principal: &str) {
         if let Ok(mut v) = principal.parse::<Decimal>() {
             v = v.round_dp_with_strategy(2, ToZero);
             if v >= Decimal::new(1000_0000_0000, 0) {
-                self.warn = Err(anyhow!("一千亿啊，土豪，还需要算吗？"))
+                self.warn = Err(anyhow!(tr!("一千亿啊，土豪，还需要算吗？")))
             } else {
+                self.edit(Field::Principal);
                 self.cfg.order.principal = v;
-                self.calc(None);
+                self.calc();
+            }
+        }
+    }
+
+    fn mode_changed(&mut self, mode: usize) {
+        self.edit(Field::Other);
+        self.cfg.order.mode = OrderMode::from(mode);
+        self.calc();
+    }
+
+    fn amount_changed(&mut self, amount: &str) {
+        if let Ok(mut v) = amount.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::new(1000_0000_0000, 0) {
+                self.warn = Err(anyhow!(tr!("一千亿啊，土豪，还需要算吗？")))
+            } else {
+                self.edit(Field::Recurring);
+                self.cfg.order.recurring.amount = v;
+                self.calc();
             }
         }
     }
 
+    fn frequency_changed(&mut self, frequency: usize) {
+        self.edit(Field::Other);
+        self.cfg.order.recurring.frequency = Frequency::from(frequency);
+        self.calc();
+    }
+
+    fn count_changed(&mut self, count: &str) {
+        if let Ok(v) = count.parse() {
+            self.edit(Field::Recurring);
+            self.cfg.order.recurring.count = v;
+            self.calc();
+        }
+    }
+
+    fn partial_toggled(&mut self, partial: bool) {
+        self.edit(Field::Other);
+        // 默认在存期中点支取一半本金，勾选后即可通过校验
+        let order = self.cfg.order;
+        self.cfg.order.withdrawal = partial.then(|| Withdrawal {
+            date: calculator::midpoint(order.save_date, order.draw_date).unwrap_or(order.save_date),
+            amount: (order.principal / Decimal::TWO).round_dp_with_strategy(2, ToZero),
+        });
+        self.calc();
+    }
+
+    fn partial_date_changed(&mut self, date: &str) {
+        if let (Ok(v), Some(mut withdrawal)) = (date.parse(), self.cfg.order.withdrawal) {
+            self.edit(Field::Withdrawal);
+            withdrawal.date = v;
+            self.cfg.order.withdrawal = Some(withdrawal);
+            self.calc();
+        }
+    }
+
+    fn partial_amount_changed(&mut self, amount: &str) {
+        if let (Ok(mut v), Some(mut withdrawal)) =
+            (amount.parse::<Decimal>(), self.cfg.order.withdrawal)
+        {
+            v = v.round_dp_with_strategy(2, ToZero);
+            self.edit(Field::Withdrawal);
+            withdrawal.amount = v;
+            self.cfg.order.withdrawal = Some(withdrawal);
+            self.calc();
+        }
+    }
+
     fn save_date_changed(&mut self, save_date: &str) {
         if let Ok(v) = save_date.parse() {
+            self.edit(Field::SaveDate);
             self.cfg.order.save_date = v;
-            self.calc(None);
+            self.calc();
         }
     }
 
     fn draw_date_changed(&mut self, draw_date: &str) {
         if let Ok(v) = draw_date.parse() {
+            self.edit(Field::DrawDate);
             self.cfg.order.draw_date = v;
-            self.calc(None);
+            self.calc();
         }
     }
 
     fn term_changed(&mut self, term: &str, row_index: usize) {
         if let Ok(v) = term.parse() {
+            self.edit(Field::Term(row_index));
             self.cfg.products[row_index].term = v;
-            self.calc(Some(row_index));
+            self.calc();
+        }
+    }
+
+    fn kind_changed(&mut self, kind: usize, row_index: usize) {
+        self.edit(Field::Other);
+        self.cfg.products[row_index].kind = ProductKind::from(kind);
+        self.calc();
+    }
+
+    fn notice_period_changed(&mut self, period: usize, row_index: usize) {
+        self.edit(Field::Other);
+        self.cfg.products[row_index].notice.period = NoticePeriod::from(period);
+        self.calc();
+    }
+
+    fn min_balance_changed(&mut self, min_balance: &str, row_index: usize) {
+        if let Ok(mut v) = min_balance.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::ZERO {
+                self.edit(Field::Notice(row_index));
+                self.cfg.products[row_index].notice.min_balance = v;
+                self.calc();
+            }
+        }
+    }
+
+    fn maturity_changed(&mut self, maturity: &str, row_index: usize) {
+        if let Ok(v) = maturity.parse() {
+            self.edit(Field::Fixed(row_index));
+            self.cfg.products[row_index].fixed.maturity = v;
+            self.calc();
+        }
+    }
+
+    fn open_from_changed(&mut self, open_from: &str, row_index: usize) {
+        if let Ok(v) = open_from.parse() {
+            self.edit(Field::Fixed(row_index));
+            self.cfg.products[row_index].fixed.open_from = v;
+            self.calc();
+        }
+    }
+
+    fn open_until_changed(&mut self, open_until: &str, row_index: usize) {
+        if let Ok(v) = open_until.parse() {
+            self.edit(Field::Fixed(row_index));
+            self.cfg.products[row_index].fixed.open_until = v;
+            self.calc();
         }
     }
 
     fn term_type_changed(&mut self, term_type: usize, row_index: usize) {
+        self.edit(Field::Other);
         self.cfg.products[row_index].term_type = TermType::from(term_type);
-        self.calc(Some(row_index));
+        self.calc();
     }
 
     fn int_rate_changed(&mut self, int_rate: &str, row_index: usize) {
         if let Ok(mut v) = int_rate.parse::<Decimal>() {
             v = v.round_dp_with_strategy(2, ToZero);
             if v > Decimal::TEN {
-                self.warn = Err(anyhow!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！"));
+                self.warn = Err(anyhow!(tr!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！")));
             } else {
+                self.edit(Field::IntRate(row_index));
                 self.cfg.products[row_index].int_rate = v;
-                self.calc(Some(row_index));
+                self.calc();
             }
         }
     }
@@ -345,30 +1053,404 @@ impl App {
         if let Ok(mut v) = bean_rate.parse::<Decimal>() {
             v = v.round_dp_with_strategy(2, ToZero);
             if v > Decimal::TEN {
-                self.warn = Err(anyhow!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！"));
+                self.warn = Err(anyhow!(tr!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！")));
             } else {
+                self.edit(Field::BeanRate(row_index));
                 self.cfg.products[row_index].bean_rate = v;
-                self.calc(Some(row_index));
+                self.calc();
+            }
+        }
+    }
+
+    fn mode_ui(&mut self, ui: &mut egui::Ui) {
+        let order = self.cfg.order;
+
+        let mut mode = order.mode as usize;
+        if ComboBox::from_id_source("存款方式")
+            .show_index(ui, &mut mode, 2, |i| OrderMode::from(i).to_string())
+            .changed()
+        {
+            self.mode_changed(mode);
+        }
+
+        if order.mode == OrderMode::Recurring {
+            let mut frequency = order.recurring.frequency as usize;
+            if ComboBox::from_id_source("存入频率")
+                .show_index(ui, &mut frequency, 2, |i| Frequency::from(i).to_string())
+                .changed()
+            {
+                self.frequency_changed(frequency);
+            }
+
+            ui.label(tr!("期数"));
+            let mut count = order.recurring.count.to_string();
+            if TextEdit::singleline(&mut count)
+                .desired_width(40.0)
+                .ui(ui)
+                .changed()
+            {
+                self.count_changed(&*count);
+            }
+
+            ui.label(tr!("按期数计算到期日，产品存期不参与计算"));
+        }
+
+        if order.mode == OrderMode::LumpSum {
+            let mut partial = order.withdrawal.is_some();
+            if ui.checkbox(&mut partial, tr!("部分提前支取")).changed() {
+                self.partial_toggled(partial);
+            }
+
+            if let Some(withdrawal) = order.withdrawal {
+                ui.label(tr!("日期"));
+                let mut date = withdrawal.date.to_string();
+                if TextEdit::singleline(&mut date)
+                    .desired_width(80.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    date.truncate(8);
+                    self.partial_date_changed(&*date);
+                }
+
+                ui.label(tr!("金额"));
+                let mut amount = format!("{:.2}", withdrawal.amount);
+                if TextEdit::singleline(&mut amount)
+                    .desired_width(100.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.partial_amount_changed(&*amount);
+                }
             }
         }
     }
 
+    fn term_ui(&mut self, ui: &mut egui::Ui, row_index: usize) {
+        let product = self.cfg.products[row_index];
+
+        let mut kind = product.kind as usize;
+        if ComboBox::from_id_source(format!("产品类型{}", row_index))
+            .width(50.0)
+            .show_index(ui, &mut kind, ProductKind::COUNT, |i| {
+                ProductKind::from(i).to_string()
+            })
+            .changed()
+        {
+            self.kind_changed(kind, row_index);
+        }
+
+        match product.kind {
+            ProductKind::Term => {
+                let mut term = product.term.to_string();
+                if TextEdit::singleline(&mut term)
+                    .desired_width(30.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.term_changed(&*term, row_index);
+                }
+
+                let mut term_type = product.term_type as usize;
+                if ComboBox::from_id_source(format!("存期类型{}", row_index))
+                    .width(40.0)
+                    .show_index(ui, &mut term_type, TermType::COUNT, |i| {
+                        TermType::from(i).to_string()
+                    })
+                    .changed()
+                {
+                    self.term_type_changed(term_type, row_index);
+                };
+            }
+            ProductKind::Fixed => {
+                let mut maturity = product.fixed.maturity.to_string();
+                if TextEdit::singleline(&mut maturity)
+                    .desired_width(70.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    maturity.truncate(8);
+                    self.maturity_changed(&*maturity, row_index);
+                }
+
+                let open = product.fixed.is_open(self.cfg.order.save_date);
+                let title = if open {
+                    RichText::new(tr!("认购期"))
+                } else {
+                    RichText::new(tr!("认购期")).color(Color32::RED)
+                };
+                ui.menu_button(title, |ui| {
+                    egui::Grid::new(format!("认购期{}", row_index)).show(ui, |ui| {
+                        ui.label(tr!("开始日期"));
+                        let mut open_from = product.fixed.open_from.to_string();
+                        if ui.text_edit_singleline(&mut open_from).changed() {
+                            open_from.truncate(8);
+                            self.open_from_changed(&*open_from, row_index);
+                        }
+                        ui.end_row();
+
+                        ui.label(tr!("结束日期"));
+                        let mut open_until = product.fixed.open_until.to_string();
+                        if ui.text_edit_singleline(&mut open_until).changed() {
+                            open_until.truncate(8);
+                            self.open_until_changed(&*open_until, row_index);
+                        }
+                        ui.end_row();
+                    });
+                    ui.label(tr!("日期为0表示不限，购买日期不在认购期内没有收益"));
+                });
+            }
+            ProductKind::Notice => {
+                let mut period = product.notice.period as usize;
+                if ComboBox::from_id_source(format!("通知期{}", row_index))
+                    .width(80.0)
+                    .show_index(ui, &mut period, 2, |i| NoticePeriod::from(i).to_string())
+                    .changed()
+                {
+                    self.notice_period_changed(period, row_index);
+                }
+
+                ui.menu_button(tr!("起存"), |ui| {
+                    ui.label(tr!("最低起存金额"));
+                    let mut min_balance = format!("{:.2}", product.notice.min_balance);
+                    if ui.text_edit_singleline(&mut min_balance).changed() {
+                        self.min_balance_changed(&*min_balance, row_index);
+                    }
+                });
+            }
+        }
+    }
+
+    fn payout_ui(&mut self, ui: &mut egui::Ui, row_index: usize) {
+        let product = self.cfg.products[row_index];
+
+        let mut frequency = product.payout.frequency.map(|f| f as usize + 1).unwrap_or(0);
+        if ComboBox::from_id_source(format!("付息频率{}", row_index))
+            .show_index(ui, &mut frequency, 3, |i| match i {
+                0 => tr!("到期付息").to_owned(),
+                i => tr!("{}付息", Frequency::from(i - 1)),
+            })
+            .changed()
+        {
+            self.payout_frequency_changed(frequency, row_index);
+        }
+
+        let mut earn_demand = product.payout.earn_demand;
+        if ui
+            .checkbox(&mut earn_demand, tr!("付出的利息按活期计息"))
+            .changed()
+        {
+            self.earn_demand_changed(earn_demand, row_index);
+        }
+
+        let mut order = self.cfg.order;
+        if product.payout.frequency.is_none() || calculator::check_date(&mut order).is_err() {
+            return;
+        }
+
+        ui.separator();
+        let req = Req::new(&order, &product);
+        let items = calculator::payouts(&req, &calculator::schedule(&req));
+        if items.is_empty() {
+            ui.label(tr!("支取日前没有到期付息"));
+            return;
+        }
+        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            egui::Grid::new(format!("付息计划{}", row_index))
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(tr!("付息日期"));
+                    ui.label(tr!("利息"));
+                    ui.label(tr!("活期利息"));
+                    ui.end_row();
+                    for item in &items {
+                        ui.label(calculator::date_to_u32(item.date).to_string());
+                        ui.label(format!("{:.2}", item.amount));
+                        ui.label(format!("{:.2}", item.demand_int));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn bean_rule_ui(&mut self, ui: &mut egui::Ui, row_index: usize) {
+        let rule = self.cfg.products[row_index].bean_rule;
+        egui::Grid::new(format!("邦豆规则{}", row_index)).show(ui, |ui| {
+            ui.label(tr!("每户上限"));
+            let mut cap = format!("{:.2}", rule.cap);
+            if ui.text_edit_singleline(&mut cap).changed() {
+                self.bean_cap_changed(&*cap, row_index);
+            }
+            ui.end_row();
+
+            ui.label(tr!("最短持有天数"));
+            let mut min_days = rule.min_days.to_string();
+            if ui.text_edit_singleline(&mut min_days).changed() {
+                self.bean_min_days_changed(&*min_days, row_index);
+            }
+            ui.end_row();
+
+            ui.label(tr!("首存奖励"));
+            let mut bonus = format!("{:.2}", rule.bonus);
+            if ui.text_edit_singleline(&mut bonus).changed() {
+                self.bean_bonus_changed(&*bonus, row_index);
+            }
+            ui.end_row();
+
+            ui.label(tr!("活动截止日期"));
+            let mut valid_until = rule.valid_until.to_string();
+            if ui.text_edit_singleline(&mut valid_until).changed() {
+                valid_until.truncate(8);
+                self.bean_valid_until_changed(&*valid_until, row_index);
+            }
+            ui.end_row();
+        });
+        ui.label(tr!("上限与截止日期为0表示不限"));
+    }
+
+    fn bean_cap_changed(&mut self, cap: &str, row_index: usize) {
+        if let Ok(mut v) = cap.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::ZERO {
+                self.edit(Field::BeanRule(row_index));
+                self.cfg.products[row_index].bean_rule.cap = v;
+                self.calc();
+            }
+        }
+    }
+
+    fn bean_min_days_changed(&mut self, min_days: &str, row_index: usize) {
+        if let Ok(v) = min_days.parse() {
+            self.edit(Field::BeanRule(row_index));
+            self.cfg.products[row_index].bean_rule.min_days = v;
+            self.calc();
+        }
+    }
+
+    fn bean_bonus_changed(&mut self, bonus: &str, row_index: usize) {
+        if let Ok(mut v) = bonus.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::ZERO {
+                self.edit(Field::BeanRule(row_index));
+                self.cfg.products[row_index].bean_rule.bonus = v;
+                self.calc();
+            }
+        }
+    }
+
+    fn bean_valid_until_changed(&mut self, valid_until: &str, row_index: usize) {
+        if let Ok(v) = valid_until.parse() {
+            self.edit(Field::BeanRule(row_index));
+            self.cfg.products[row_index].bean_rule.valid_until = v;
+            self.calc();
+        }
+    }
+
+    fn bean_ratio_changed(&mut self, ratio: &str) {
+        if let Ok(mut v) = ratio.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(4, ToZero);
+            if v >= Decimal::ZERO {
+                self.edit(Field::BeanValue);
+                self.cfg.bean_value.ratio = v;
+            }
+        }
+    }
+
+    fn bean_discount_changed(&mut self, discount: &str) {
+        if let Ok(mut v) = discount.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v < Decimal::ZERO || v > Decimal::ONE_HUNDRED {
+                self.warn = Err(anyhow!(tr!("折价要在0到100之间！")));
+            } else {
+                self.edit(Field::BeanValue);
+                self.cfg.bean_value.discount = v;
+            }
+        }
+    }
+
+    fn inflation_rate_changed(&mut self, rate: &str) {
+        if let Ok(mut v) = rate.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v <= -Decimal::ONE_HUNDRED || v > Decimal::ONE_HUNDRED {
+                self.warn = Err(anyhow!(tr!("通胀率要在-100到100之间！")));
+            } else {
+                self.edit(Field::Inflation);
+                self.cfg.inflation.rate = v;
+            }
+        }
+    }
+
+    fn load_cpi(&mut self) {
+        if let Some(path) = scenario::pick_cpi() {
+            match scenario::load_cpi(&path) {
+                Ok(series) => {
+                    self.edit(Field::Other);
+                    self.cfg.inflation.series = series;
+                }
+                Err(e) => self.warn = Err(e),
+            }
+        }
+    }
+
+    fn sort_by_total(&mut self) {
+        self.edit(Field::Other);
+        let value = self.cfg.bean_value;
+        self.cfg.products.sort_by(|a, b| {
+            value
+                .total(b.interest, b.bean_int)
+                .cmp(&value.total(a.interest, a.bean_int))
+        });
+    }
+
+    fn payout_frequency_changed(&mut self, frequency: usize, row_index: usize) {
+        self.edit(Field::Other);
+        self.cfg.products[row_index].payout.frequency = match frequency {
+            0 => None,
+            i => Some(Frequency::from(i - 1)),
+        };
+        self.calc();
+    }
+
+    fn earn_demand_changed(&mut self, earn_demand: bool, row_index: usize) {
+        self.edit(Field::Other);
+        self.cfg.products[row_index].payout.earn_demand = earn_demand;
+        self.calc();
+    }
+
     fn renew_type_changed(&mut self, renew_type: usize, row_index: usize) {
+        self.edit(Field::Other);
         self.cfg.products[row_index].renew_type = RenewType::from(renew_type);
-        self.calc(Some(row_index));
+        self.calc();
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn setup_custom_fonts(ctx: &egui::Context) {
+    install_font(ctx, egui::FontData::from_static(FONT));
+}
+
+// 字体有十几兆，网页版不打包进 wasm，启动后从页面同目录下载，下载完成前中文显示为方框
+#[cfg(target_arch = "wasm32")]
 fn setup_custom_fonts(ctx: &egui::Context) {
+    let ctx = ctx.clone();
+    ehttp::fetch(ehttp::Request::get(FONT_URL), move |res| {
+        // 下载失败时保留默认字体
+        if let Ok(res) = res {
+            if res.ok {
+                install_font(&ctx, egui::FontData::from_owned(res.bytes));
+                ctx.request_repaint();
+            }
+        }
+    });
+}
+
+fn install_font(ctx: &egui::Context, font: egui::FontData) {
     // Start with the default fonts (we will be adding to them rather than replacing them).
     let mut fonts = egui::FontDefinitions::default();
 
     // Install my own font (maybe supporting non-latin characters).
     // .ttf and .otf files supported.
-    fonts.font_data.insert(
-        "simkai".to_owned(),
-        egui::FontData::from_static(include_bytes!("../../resource/simkai.ttf")),
-    );
+    fonts.font_data.insert("simkai".to_owned(), font);
 
     let entry = fonts
         .families
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox, RichText, TextEdit, Widget};
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy::ToZero;
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::Holding;
+use crate::app::history::Field;
+use crate::app::i18n::tr;
+use crate::app::App;
+
+// 各银行的存款保险敞口，持有存款、支取日期或上限变化前重复使用
+pub struct Exposures {
+    key: Vec<(String, Req)>,
+    limit: Decimal,
+    banks: Vec<Exposure>,
+}
+
+// 某银行各到期日中最高的本息合计，以及首次超出上限的日期
+struct Exposure {
+    bank: String,
+    peak: Decimal,
+    peak_date: u32,
+    breach: Option<u32>,
+}
+
+impl Exposures {
+    // 购买日期有误的存款不参与计算
+    fn key(holdings: &[Holding], draw_date: u32) -> Vec<(String, Req)> {
+        holdings
+            .iter()
+            .filter(|h| calculator::u32_to_date(h.save_date).is_ok())
+            .map(|h| (h.bank.clone(), Req::new(&h.order(draw_date), &h.product)))
+            .collect()
+    }
+
+    // 每笔存款只算一次明细，得到每期结束时的本息合计；
+    // 银行在其任一笔存款的每期结束日累计各笔存款当日已结算的本息。
+    // 支取日期前没有到期的，按首个到期日估算
+    fn new(key: Vec<(String, Req)>, limit: Decimal) -> Self {
+        let mut holdings: BTreeMap<&str, Vec<(Req, Vec<(u32, Decimal)>)>> = BTreeMap::new();
+        for (bank, req) in &key {
+            let mut periods = calculator::schedule(req);
+            if !periods.iter().any(|p| p.matured) {
+                if let Some(date) = calculator::first_maturity(req) {
+                    periods = calculator::schedule(&Req {
+                        draw_date: calculator::date_to_u32(date),
+                        ..*req
+                    });
+                }
+            }
+            let amounts = periods
+                .iter()
+                .map(|p| {
+                    let amount = req.principal.checked_add(p.interest).unwrap_or(Decimal::MAX);
+                    (calculator::date_to_u32(p.end), amount)
+                })
+                .collect();
+            holdings.entry(bank).or_default().push((*req, amounts));
+        }
+
+        let banks = holdings
+            .into_iter()
+            .filter_map(|(bank, holdings)| {
+                let mut dates: Vec<u32> = holdings
+                    .iter()
+                    .flat_map(|(_, amounts)| amounts.iter().map(|(date, _)| *date))
+                    .collect();
+                dates.sort_unstable();
+                dates.dedup();
+
+                let mut exposure: Option<Exposure> = None;
+                for date in dates {
+                    let amount = holdings
+                        .iter()
+                        .filter(|(req, _)| req.save_date <= date)
+                        .map(|(req, amounts)| match amounts.iter().rfind(|(d, _)| *d <= date) {
+                            Some((_, amount)) => *amount,
+                            None => req.principal,
+                        })
+                        .fold(Decimal::ZERO, |a, b| a.checked_add(b).unwrap_or(Decimal::MAX));
+                    let e = exposure.get_or_insert(Exposure {
+                        bank: bank.to_owned(),
+                        peak: amount,
+                        peak_date: date,
+                        breach: None,
+                    });
+                    if amount > e.peak {
+                        e.peak = amount;
+                        e.peak_date = date;
+                    }
+                    if amount > limit && e.breach.is_none() {
+                        e.breach = Some(date);
+                    }
+                }
+                exposure
+            })
+            .collect();
+
+        Self { key, limit, banks }
+    }
+}
+
+impl App {
+    pub(super) fn show_portfolio(&mut self, ctx: &egui::Context) {
+        let mut open = self.portfolio_open;
+        egui::Window::new(tr!("存款保险"))
+            .id(egui::Id::new("portfolio"))
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("每家银行保障上限"));
+                    let mut limit = format!("{:.2}", self.cfg.portfolio.limit);
+                    if TextEdit::singleline(&mut limit)
+                        .desired_width(100.0)
+                        .ui(ui)
+                        .changed()
+                    {
+                        self.limit_changed(&*limit);
+                    }
+                });
+                if let Some(e) = &self.portfolio_error {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+                ui.separator();
+
+                self.holdings_editor(ui);
+                ui.separator();
+                self.exposure_report(ui);
+            });
+        self.portfolio_open = open;
+    }
+
+    fn holdings_editor(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+
+        egui::Grid::new("持有存款").striped(true).show(ui, |ui| {
+            ui.label(tr!("银行"));
+            ui.label(tr!("本金"));
+            ui.label(tr!("购买日期"));
+            ui.label(tr!("产品"));
+            if ui.button(tr!("添加")).clicked() {
+                self.edit(Field::Other);
+                self.cfg.portfolio.holdings.push(Holding {
+                    bank: tr!("银行").to_owned(),
+                    principal: self.cfg.order.principal,
+                    save_date: self.cfg.order.save_date,
+                    product: self.cfg.products.first().copied().unwrap_or_default(),
+                });
+            }
+            ui.end_row();
+
+            for i in 0..self.cfg.portfolio.holdings.len() {
+                let holding = self.cfg.portfolio.holdings[i].clone();
+
+                let mut bank = holding.bank.clone();
+                if TextEdit::singleline(&mut bank)
+                    .desired_width(100.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.edit(Field::Holding(i));
+                    self.cfg.portfolio.holdings[i].bank = bank;
+                }
+
+                let mut principal = format!("{:.2}", holding.principal);
+                if TextEdit::singleline(&mut principal)
+                    .desired_width(100.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    self.holding_principal_changed(&*principal, i);
+                }
+
+                let mut save_date = holding.save_date.to_string();
+                if TextEdit::singleline(&mut save_date)
+                    .desired_width(80.0)
+                    .ui(ui)
+                    .changed()
+                {
+                    save_date.truncate(8);
+                    self.holding_save_date_changed(&*save_date, i);
+                }
+
+                // 从产品列表中选择，选中后复制到持有存款中
+                let mut selected = None;
+                ComboBox::from_id_source(format!("持有产品{}", i))
+                    .selected_text(holding.product.name())
+                    .show_ui(ui, |ui| {
+                        for (j, product) in self.cfg.products.iter().enumerate() {
+                            if ui.selectable_label(false, product.name()).clicked() {
+                                selected = Some(j);
+                            }
+                        }
+                    });
+                if let Some(j) = selected {
+                    self.edit(Field::Other);
+                    self.cfg.portfolio.holdings[i].product = self.cfg.products[j];
+                }
+
+                if ui.button(tr!("删除")).clicked() {
+                    removed = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = removed {
+            self.edit(Field::Other);
+            self.cfg.portfolio.holdings.remove(i);
+        }
+    }
+
+    fn exposure_report(&mut self, ui: &mut egui::Ui) {
+        let draw_date = self.cfg.order.draw_date;
+        if calculator::u32_to_date(draw_date).is_err() {
+            ui.label(tr!("日期有误"));
+            return;
+        }
+
+        let key = Exposures::key(&self.cfg.portfolio.holdings, draw_date);
+        let limit = self.cfg.portfolio.limit;
+        let stale = !matches!(&self.exposures, Some(e) if e.key == key && e.limit == limit);
+        if stale {
+            self.exposures = Some(Exposures::new(key, limit));
+        }
+        let exposures = match &self.exposures {
+            Some(exposures) => exposures,
+            None => return,
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("存款保险敞口").striped(true).show(ui, |ui| {
+                ui.label(tr!("银行"));
+                ui.label(tr!("最高本息合计"));
+                ui.label(tr!("日期"));
+                ui.label(tr!("超出上限"));
+                ui.label(tr!("首次超出日期"));
+                ui.end_row();
+
+                for e in &exposures.banks {
+                    ui.label(&e.bank);
+                    if e.peak > limit {
+                        ui.label(
+                            RichText::new(format!("{:.2}", e.peak))
+                                .strong()
+                                .color(Color32::RED),
+                        );
+                        ui.label(e.peak_date.to_string());
+                        ui.label(
+                            RichText::new(format!("{:.2}", e.peak - limit)).color(Color32::RED),
+                        );
+                    } else {
+                        ui.label(format!("{:.2}", e.peak));
+                        ui.label(e.peak_date.to_string());
+                        ui.label("-");
+                    }
+                    match e.breach {
+                        Some(date) => ui.label(RichText::new(date.to_string()).color(Color32::RED)),
+                        None => ui.label("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn limit_changed(&mut self, limit: &str) {
+        if let Ok(mut v) = limit.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::ZERO {
+                self.edit(Field::Portfolio);
+                self.cfg.portfolio.limit = v;
+            }
+        }
+    }
+
+    fn holding_principal_changed(&mut self, principal: &str, index: usize) {
+        if let Ok(mut v) = principal.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::new(1000_0000_0000, 0) {
+                self.portfolio_error = Some(anyhow!(tr!("一千亿啊，土豪，还需要算吗？")))
+            } else {
+                self.portfolio_error = None;
+                self.edit(Field::Holding(index));
+                self.cfg.portfolio.holdings[index].principal = v;
+            }
+        }
+    }
+
+    fn holding_save_date_changed(&mut self, save_date: &str, index: usize) {
+        if let Ok(v) = save_date.parse() {
+            self.edit(Field::Holding(index));
+            self.cfg.portfolio.holdings[index].save_date = v;
+        }
+    }
+}
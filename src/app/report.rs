@@ -0,0 +1,350 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::{Config, OrderMode, Product};
+use crate::app::i18n::tr;
+
+// 报告中的一张表
+struct Table {
+    title: String,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+// 与输出格式无关的报告内容，HTML 和 PDF 按同样的顺序排版
+pub struct Report {
+    time: String,
+    order: Vec<(String, String)>,
+    tables: Vec<Table>,
+    notes: Vec<String>,
+}
+
+impl Report {
+    // 勾选的产品，没有勾选则包括全部产品
+    pub fn new(cfg: &Config) -> Result<Self> {
+        let mut order = cfg.order;
+        calculator::check_date(&mut order)?;
+        calculator::check_withdrawal(&order)?;
+
+        let selected: Vec<&Product> = cfg.products.iter().filter(|p| p.selected).collect();
+        let products = if selected.is_empty() {
+            cfg.products.iter().collect()
+        } else {
+            selected
+        };
+        if products.is_empty() {
+            bail!(tr!("没有可以输出的产品！"))
+        }
+
+        let mut info = vec![(tr!("存款方式").to_owned(), order.mode.to_string())];
+        match order.mode {
+            OrderMode::LumpSum => {
+                info.push((tr!("本金").to_owned(), format!("{:.2}", order.principal)));
+                if let Some(withdrawal) = order.withdrawal {
+                    info.push((
+                        tr!("部分提前支取").to_owned(),
+                        tr!(
+                            "{} 支取 {}",
+                            withdrawal.date,
+                            format!("{:.2}", withdrawal.amount)
+                        ),
+                    ));
+                }
+            }
+            OrderMode::Recurring => {
+                let recurring = order.recurring;
+                info.push((
+                    tr!("每期金额").to_owned(),
+                    tr!(
+                        "{}（{}，共{}期）",
+                        format!("{:.2}", recurring.amount),
+                        recurring.frequency,
+                        recurring.count
+                    ),
+                ));
+            }
+        }
+        info.push((tr!("购买日期").to_owned(), order.save_date.to_string()));
+        info.push((tr!("支取日期").to_owned(), order.draw_date.to_string()));
+        info.push((tr!("天数").to_owned(), order.days.to_string()));
+
+        let value = cfg.bean_value;
+        let mut summary = Table {
+            title: tr!("收益汇总").to_owned(),
+            header: [
+                tr!("产品"),
+                tr!("利率(%)"),
+                tr!("邦豆利率(%)"),
+                tr!("利息"),
+                tr!("邦豆利息"),
+                tr!("总收益"),
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            rows: vec![],
+        };
+        let mut tables = vec![];
+        let mut notes = vec![];
+        for product in products {
+            let req = Req::new(&order, product);
+            let (interest, bean_int) = calculator::calc(&req);
+            let name = product.name();
+            summary.rows.push(vec![
+                name.clone(),
+                format!("{:.2}", product.int_rate),
+                format!("{:.2}", product.bean_rate),
+                format!("{:.2}", interest),
+                format!("{:.2}", bean_int),
+                format!("{:.2}", value.total(interest, bean_int)),
+            ]);
+            if let Err(e) = calculator::check_notice(&order, product) {
+                notes.push(format!("{}{}{}", name, tr!("："), e));
+            }
+
+            let periods = calculator::schedule(&req);
+            tables.push(Table {
+                title: tr!("{} 明细", name),
+                header: [
+                    tr!("期次"),
+                    tr!("起始日"),
+                    tr!("结束日"),
+                    tr!("本金"),
+                    tr!("累计收益"),
+                    tr!("累计邦豆利息"),
+                    tr!("状态"),
+                ]
+                .map(str::to_owned)
+                .to_vec(),
+                rows: periods
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        vec![
+                            (i + 1).to_string(),
+                            calculator::date_to_u32(p.start).to_string(),
+                            calculator::date_to_u32(p.end).to_string(),
+                            format!("{:.2}", p.principal),
+                            format!("{:.2}", p.interest),
+                            format!("{:.2}", p.bean_int),
+                            if p.matured { tr!("到期") } else { tr!("未到期") }.to_owned(),
+                        ]
+                    })
+                    .collect(),
+            });
+
+            let payouts = calculator::payouts(&req, &periods);
+            if !payouts.is_empty() {
+                tables.push(Table {
+                    title: tr!("{} 付息", name),
+                    header: [tr!("付息日"), tr!("利息"), tr!("活期利息")]
+                        .map(str::to_owned)
+                        .to_vec(),
+                    rows: payouts
+                        .iter()
+                        .map(|p| {
+                            vec![
+                                calculator::date_to_u32(p.date).to_string(),
+                                format!("{:.2}", p.amount),
+                                format!("{:.2}", p.demand_int),
+                            ]
+                        })
+                        .collect(),
+                });
+            }
+        }
+        tables.insert(0, summary);
+
+        notes.splice(
+            0..0,
+            [
+                tr!("计息天数按实际天数，年利率按360天折算为日利率").to_owned(),
+                tr!(
+                    "提前支取及未存满存期的部分按活期利率{}%计息",
+                    format!("{:.2}", calculator::DEMAND_RATE)
+                ),
+                tr!("利息四舍五入到分，邦豆利息2位小数之后舍去").to_owned(),
+                tr!(
+                    "总收益 = 利息 + 邦豆利息 × {}元 × (1 - {}%)",
+                    value.ratio,
+                    format!("{:.2}", value.discount)
+                ),
+            ],
+        );
+
+        Ok(Self {
+            time: now(),
+            order: info,
+            tables,
+            notes,
+        })
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = escape(tr!("存款收益计算报告"));
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n\
+             body { font-family: \"KaiTi\", \"STKaiti\", serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+             th, td { border: 1px solid #999; padding: 4px 10px; text-align: right; }\n\
+             th { background: #eee; }\n\
+             @media print { h2 { page-break-after: avoid; } table { page-break-inside: auto; } }\n\
+             </style>\n",
+        );
+        let _ = writeln!(html, "<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>");
+        let _ = writeln!(html, "<p>{}</p>", escape(&tr!("生成时间：{}", self.time)));
+
+        let _ = writeln!(html, "<h2>{}</h2>\n<table>", escape(tr!("存款信息")));
+        for (k, v) in &self.order {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", escape(k), escape(v));
+        }
+        html.push_str("</table>\n");
+
+        for table in &self.tables {
+            let _ = writeln!(html, "<h2>{}</h2>\n<table>", escape(&table.title));
+            html.push_str("<tr>");
+            for h in &table.header {
+                let _ = write!(html, "<th>{}</th>", escape(h));
+            }
+            html.push_str("</tr>\n");
+            for row in &table.rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(html, "<td>{}</td>", escape(cell));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+
+        let _ = writeln!(html, "<h2>{}</h2>\n<ul>", escape(tr!("计算说明")));
+        for note in &self.notes {
+            let _ = writeln!(html, "<li>{}</li>", escape(note));
+        }
+        html.push_str("</ul>\n</body>\n</html>\n");
+        html
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_pdf(&self) -> Result<Vec<u8>> {
+        use printpdf::{IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+
+        const WIDTH: f64 = 210.0;
+        const HEIGHT: f64 = 297.0;
+        const MARGIN: f64 = 15.0;
+        const LINE: f64 = 6.0;
+
+        // 逐行排版，写满一页自动换页
+        struct Writer {
+            doc: PdfDocumentReference,
+            font: IndirectFontRef,
+            layer: PdfLayerReference,
+            y: f64,
+        }
+
+        impl Writer {
+            fn line(&mut self, cells: &[(f64, &str)], size: f64) {
+                if self.y < MARGIN {
+                    let (page, layer) = self.doc.add_page(Mm(WIDTH), Mm(HEIGHT), "内容");
+                    self.layer = self.doc.get_page(page).get_layer(layer);
+                    self.y = HEIGHT - MARGIN;
+                }
+                for (x, text) in cells {
+                    self.layer.use_text(*text, size, Mm(*x), Mm(self.y), &self.font);
+                }
+                self.y -= LINE * size / 10.0;
+            }
+
+            fn table(&mut self, header: &[String], rows: &[Vec<String>]) {
+                let step = (WIDTH - MARGIN * 2.0) / header.len() as f64;
+                let x = |i: usize| MARGIN + step * i as f64;
+                let cells: Vec<(f64, &str)> =
+                    header.iter().enumerate().map(|(i, h)| (x(i), h.as_str())).collect();
+                self.line(&cells, 9.0);
+                for row in rows {
+                    let cells: Vec<(f64, &str)> =
+                        row.iter().enumerate().map(|(i, c)| (x(i), c.as_str())).collect();
+                    self.line(&cells, 9.0);
+                }
+                self.y -= LINE / 2.0;
+            }
+        }
+
+        let (doc, page, layer) =
+            PdfDocument::new(tr!("存款收益计算报告"), Mm(WIDTH), Mm(HEIGHT), "内容");
+        let font = doc
+            .add_external_font(super::FONT)
+            .map_err(|e| anyhow!(tr!("生成PDF失败！{}", e)))?;
+        let layer = doc.get_page(page).get_layer(layer);
+        let mut w = Writer {
+            doc,
+            font,
+            layer,
+            y: HEIGHT - MARGIN,
+        };
+
+        w.line(&[(MARGIN, tr!("存款收益计算报告"))], 16.0);
+        w.line(&[(MARGIN, &tr!("生成时间：{}", self.time))], 9.0);
+        w.y -= LINE / 2.0;
+
+        w.line(&[(MARGIN, tr!("存款信息"))], 12.0);
+        for (k, v) in &self.order {
+            w.line(&[(MARGIN, k), (MARGIN + 40.0, v)], 9.0);
+        }
+        w.y -= LINE / 2.0;
+
+        for table in &self.tables {
+            w.line(&[(MARGIN, &table.title)], 12.0);
+            w.table(&table.header, &table.rows);
+        }
+
+        w.line(&[(MARGIN, tr!("计算说明"))], 12.0);
+        for note in &self.notes {
+            w.line(&[(MARGIN, &format!("· {note}"))], 9.0);
+        }
+
+        w.doc
+            .save_to_bytes()
+            .map_err(|e| anyhow!(tr!("生成PDF失败！{}", e)))
+    }
+}
+
+// 按扩展名输出 HTML 或 PDF
+pub fn export(cfg: &Config, path: &Path) -> Result<()> {
+    let report = Report::new(cfg)?;
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let bytes = match ext.as_str() {
+        "html" | "htm" => report.to_html().into_bytes(),
+        #[cfg(not(target_arch = "wasm32"))]
+        "pdf" => report.to_pdf()?,
+        _ => bail!(tr!("报告只支持HTML或PDF格式！")),
+    };
+    fs::write(path, bytes).map_err(|e| anyhow!(tr!("导出报告失败！{}", e)))
+}
+
+fn now() -> String {
+    let now = OffsetDateTime::now_utc().to_offset(UtcOffset::from_hms(8, 0, 0).unwrap());
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::Decimal;
+
+use crate::app::calculator;
+use crate::app::config::{Config, Cpi};
+use crate::app::i18n::tr;
+
+const RECENT_MAX: usize = 10;
+
+// 浏览器中没有本地文件，文件相关的菜单和按钮不显示
+pub const HAS_FILES: bool = cfg!(not(target_arch = "wasm32"));
+
+pub fn load(path: &Path) -> Result<Config> {
+    let text = fs::read_to_string(path).map_err(|e| anyhow!(tr!("打开方案失败！{}", e)))?;
+    let cfg = if is_toml(path) {
+        toml::from_str(&text).map_err(|e| anyhow!(tr!("方案格式有误！{}", e)))?
+    } else {
+        serde_json::from_str(&text).map_err(|e| anyhow!(tr!("方案格式有误！{}", e)))?
+    };
+    Ok(cfg)
+}
+
+pub fn save(path: &Path, cfg: &Config) -> Result<()> {
+    let text = if is_toml(path) {
+        toml::to_string_pretty(cfg).map_err(|e| anyhow!(tr!("保存方案失败！{}", e)))?
+    } else {
+        serde_json::to_string_pretty(cfg).map_err(|e| anyhow!(tr!("保存方案失败！{}", e)))?
+    };
+    fs::write(path, text).map_err(|e| anyhow!(tr!("保存方案失败！{}", e)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_open() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter(tr!("方案"), &["json", "toml"])
+        .pick_file()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_save() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("JSON", &["json"])
+        .add_filter("TOML", &["toml"])
+        .set_file_name(tr!("方案.json"))
+        .save_file()
+}
+
+// 导出文件时选择保存位置，name 带扩展名
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_export(filter: &str, ext: &str, name: &str) -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter(filter, &[ext])
+        .set_file_name(name)
+        .save_file()
+}
+
+// 通胀序列每行为“日期,年通胀率(%)”，可以有表头，也可以用制表符分隔
+pub fn load_cpi(path: &Path) -> Result<Vec<Cpi>> {
+    let text = fs::read_to_string(path).map_err(|e| anyhow!(tr!("打开通胀序列失败！{}", e)))?;
+    let mut series = vec![];
+    for (i, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut cols = line.split(|c| c == ',' || c == '\t').map(str::trim);
+        let date = cols.next().and_then(|d| d.parse::<u32>().ok());
+        let rate = cols.next().and_then(|r| r.parse::<Decimal>().ok());
+        match (date, rate) {
+            (Some(date), Some(rate)) => {
+                calculator::u32_to_date(date)?;
+                if rate <= -Decimal::ONE_HUNDRED {
+                    bail!(tr!("第{}行通胀率有误！", i + 1))
+                }
+                series.push(Cpi { date, rate })
+            }
+            _ if i == 0 => continue,
+            _ => bail!(tr!("第{}行格式有误，应为“日期,年通胀率”！", i + 1)),
+        }
+    }
+    if series.is_empty() {
+        bail!(tr!("通胀序列为空！"))
+    }
+    series.sort_by_key(|cpi| cpi.date);
+    series.dedup_by_key(|cpi| cpi.date);
+    Ok(series)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_cpi() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("CSV", &["csv", "txt"])
+        .pick_file()
+}
+
+// 最近打开的文件放在最前面，去重并限制数量
+pub fn push_recent(recent: &mut Vec<PathBuf>, path: &Path) {
+    recent.retain(|p| p != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(RECENT_MAX);
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn pick_open() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn pick_save() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn pick_export(_filter: &str, _ext: &str, _name: &str) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn pick_cpi() -> Option<PathBuf> {
+    None
+}
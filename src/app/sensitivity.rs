@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox, ScrollArea, TextEdit, Widget};
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy::ToZero;
+use time::Duration;
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::{Order, Product};
+use crate::app::i18n::tr;
+use crate::app::scenario;
+use crate::app::worker::Lane;
+use crate::app::App;
+
+// 单个产品在 本金 × 支取日期 上的收益表，零存整取时本金为每期金额
+pub struct Sensitivity {
+    order: Order,
+    product: Product,
+    principals: Vec<Decimal>,
+    draw_dates: Vec<u32>,
+    // 列标题，按持有天数时显示天数
+    columns: Vec<String>,
+    by_days: bool,
+    cells: Option<Vec<Vec<(Decimal, Decimal)>>>,
+}
+
+impl Sensitivity {
+    fn new(
+        order: &Order,
+        product: Product,
+        principals: &str,
+        columns: &str,
+        by_days: bool,
+    ) -> Result<Self> {
+        let principals = split(principals)
+            .map(|s| {
+                let v = s
+                    .parse::<Decimal>()
+                    .map(|v| v.round_dp_with_strategy(2, ToZero))
+                    .map_err(|e| anyhow!(tr!("本金有误！{} {}", s, e)))?;
+                if v >= Decimal::new(1000_0000_0000, 0) {
+                    bail!(tr!("一千亿啊，土豪，还需要算吗？"))
+                }
+                Ok(v)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let save_date = calculator::u32_to_date(order.save_date)?;
+        let mut draw_dates = vec![];
+        let mut titles = vec![];
+        for s in split(columns) {
+            let draw_date = if by_days {
+                let days = s.parse::<i64>().map_err(|e| anyhow!(tr!("天数有误！{} {}", s, e)))?;
+                calculator::date_to_u32(save_date.saturating_add(Duration::days(days)))
+            } else {
+                s.parse::<u32>().map_err(|e| anyhow!(tr!("日期有误！{} {}", s, e)))?
+            };
+            calculator::check_date(&mut Order {
+                draw_date,
+                ..*order
+            })?;
+            draw_dates.push(draw_date);
+            titles.push(s.to_owned());
+        }
+
+        if principals.is_empty() || draw_dates.is_empty() {
+            bail!(tr!("请输入本金与支取日期"))
+        }
+
+        Ok(Self {
+            order: *order,
+            product,
+            principals,
+            draw_dates,
+            columns: titles,
+            by_days,
+            cells: None,
+        })
+    }
+
+    fn req(&self, principal: Decimal, draw_date: u32) -> Req {
+        let mut order = Order {
+            draw_date,
+            ..self.order
+        };
+        order.set_amount(principal);
+        Req::new(&order, &self.product)
+    }
+
+    fn reqs(&self) -> Vec<Req> {
+        self.principals
+            .iter()
+            .flat_map(|p| self.draw_dates.iter().map(|d| self.req(*p, *d)))
+            .collect()
+    }
+
+    fn to_csv(&self) -> String {
+        let column = if self.by_days { tr!("持有天数") } else { tr!("支取日期") };
+        let mut csv = format!("{}\\{}", self.order.amount_label(), column);
+        for title in &self.columns {
+            csv.push_str(&tr!(",{}利息,{}邦豆利息", title, title));
+        }
+        csv.push('\n');
+
+        if let Some(cells) = &self.cells {
+            for (principal, row) in self.principals.iter().zip(cells) {
+                csv.push_str(&format!("{:.2}", principal));
+                for (interest, bean_int) in row {
+                    csv.push_str(&format!(",{:.2},{:.2}", interest, bean_int));
+                }
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+}
+
+impl App {
+    pub(super) fn show_sensitivity(&mut self, ctx: &egui::Context) {
+        let mut open = self.sensitivity_open;
+        egui::Window::new(tr!("收益测算表"))
+            .id(egui::Id::new("sensitivity"))
+            .open(&mut open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("测算条件").show(ui, |ui| {
+                    ui.label(tr!("产品"));
+                    if self.cfg.products.is_empty() {
+                        ui.label(tr!("无"));
+                    } else {
+                        let mut index = self
+                            .sensitivity_product
+                            .min(self.cfg.products.len() - 1);
+                        let products = &self.cfg.products;
+                        ComboBox::from_id_source("测算产品").show_index(
+                            ui,
+                            &mut index,
+                            products.len(),
+                            |i| products[i].name(),
+                        );
+                        self.sensitivity_product = index;
+                    }
+                    ui.end_row();
+
+                    ui.label(self.cfg.order.amount_label());
+                    TextEdit::singleline(&mut self.sensitivity_principals)
+                        .hint_text(tr!("多个用逗号分隔"))
+                        .ui(ui);
+                    ui.end_row();
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.sensitivity_by_days, false, tr!("支取日期"));
+                        ui.radio_value(&mut self.sensitivity_by_days, true, tr!("持有天数"));
+                    });
+                    TextEdit::singleline(&mut self.sensitivity_columns)
+                        .hint_text(tr!("多个用逗号分隔"))
+                        .ui(ui);
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("计算")).clicked() {
+                        self.start_sensitivity();
+                    }
+                    let ready = matches!(&self.sensitivity, Some(s) if s.cells.is_some());
+                    if scenario::HAS_FILES
+                        && ui.add_enabled(ready, egui::Button::new(tr!("导出CSV"))).clicked()
+                    {
+                        self.export_sensitivity();
+                    }
+                });
+                if let Some(e) = &self.sensitivity_error {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+
+                self.poll_sensitivity();
+
+                let table = match &self.sensitivity {
+                    Some(table) => table,
+                    None => return,
+                };
+                let cells = match &table.cells {
+                    Some(cells) => cells,
+                    None => {
+                        ui.label(tr!("计算中…"));
+                        return;
+                    }
+                };
+
+                ui.separator();
+                ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("测算结果").striped(true).show(ui, |ui| {
+                        ui.label(table.order.amount_label());
+                        for title in &table.columns {
+                            ui.label(title);
+                        }
+                        ui.end_row();
+
+                        for (principal, row) in table.principals.iter().zip(cells) {
+                            ui.label(format!("{:.2}", principal));
+                            for (interest, bean_int) in row {
+                                ui.label(format!("{:.2} / {:.2}", interest, bean_int));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        self.sensitivity_open = open;
+    }
+
+    fn start_sensitivity(&mut self) {
+        let product = match self.cfg.products.get(self.sensitivity_product) {
+            Some(product) => *product,
+            None => return,
+        };
+
+        self.sensitivity_error = None;
+        match Sensitivity::new(
+            &self.cfg.order,
+            product,
+            &self.sensitivity_principals,
+            &self.sensitivity_columns,
+            self.sensitivity_by_days,
+        ) {
+            Ok(table) => {
+                self.request(Lane::Sensitivity, table.reqs());
+                self.sensitivity = Some(table);
+            }
+            Err(e) => {
+                self.sensitivity = None;
+                self.sensitivity_error = Some(e);
+            }
+        }
+    }
+
+    // 结果全部算完后再填表
+    fn poll_sensitivity(&mut self) {
+        let table = match &mut self.sensitivity {
+            Some(table) if table.cells.is_none() => table,
+            _ => return,
+        };
+
+        let mut cells = Vec::with_capacity(table.principals.len());
+        for principal in &table.principals {
+            let mut row = Vec::with_capacity(table.draw_dates.len());
+            for draw_date in &table.draw_dates {
+                let req = table.req(*principal, *draw_date);
+                match self.batches.get(Lane::Sensitivity, &req) {
+                    Some(res) => row.push(res),
+                    None => return,
+                }
+            }
+            cells.push(row);
+        }
+        table.cells = Some(cells);
+    }
+
+    fn export_sensitivity(&mut self) {
+        let table = match &self.sensitivity {
+            Some(table) => table,
+            None => return,
+        };
+        let path = match scenario::pick_export("CSV", "csv", tr!("收益测算表.csv")) {
+            Some(path) => path,
+            None => return,
+        };
+
+        self.sensitivity_error = write_csv(&path, &table.to_csv()).err();
+    }
+}
+
+fn split(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| c == ',' || c == '，' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+}
+
+fn write_csv(path: &Path, csv: &str) -> Result<()> {
+    // 带 BOM 以便 Excel 正确识别中文
+    fs::write(path, format!("\u{feff}{csv}")).map_err(|e| anyhow!(tr!("导出失败！{}", e)))
+}
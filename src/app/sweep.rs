@@ -0,0 +1,251 @@
+use anyhow::{bail, Result};
+use eframe::egui;
+use eframe::egui::plot::{Legend, Line, Plot, Value, Values};
+use eframe::egui::{Color32, ScrollArea, TextEdit, Widget};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::{Order, Product};
+use crate::app::i18n::tr;
+use crate::app::worker::Lane;
+use crate::app::App;
+
+// 按支取日期逐日计算所选产品的收益
+pub struct Sweep {
+    order: Order,
+    dates: Vec<u32>,
+    products: Vec<Product>,
+    // 每个产品每个支取日期的总收益，全部算完后填充
+    totals: Option<Vec<Vec<Decimal>>>,
+    crossovers: Vec<Crossover>,
+}
+
+// 从 date 起 winner 的收益超过 loser
+pub struct Crossover {
+    date: u32,
+    winner: usize,
+    loser: usize,
+}
+
+impl Sweep {
+    fn new(order: &Order, products: Vec<Product>, from: u32, to: u32) -> Result<Self> {
+        if from < order.save_date {
+            bail!(tr!("支取日期不能早于购买日期！"))
+        }
+        let mut order = Order {
+            draw_date: to,
+            ..*order
+        };
+        calculator::check_date(&mut order)?;
+        calculator::check_date(&mut Order {
+            draw_date: from,
+            ..order
+        })?;
+        if from > to {
+            bail!(tr!("穿越时空？"))
+        }
+
+        let mut dates = vec![];
+        let mut date = calculator::u32_to_date(from)?;
+        let to = calculator::u32_to_date(to)?;
+        while date <= to {
+            dates.push(calculator::date_to_u32(date));
+            // 9999-12-31 没有下一天
+            match date.next_day() {
+                Some(next) => date = next,
+                None => break,
+            }
+        }
+
+        Ok(Self {
+            order,
+            dates,
+            products,
+            totals: None,
+            crossovers: vec![],
+        })
+    }
+
+    fn req(&self, product: &Product, draw_date: u32) -> Req {
+        Req::new(
+            &Order {
+                draw_date,
+                ..self.order
+            },
+            product,
+        )
+    }
+
+    fn name(&self, index: usize) -> String {
+        self.products[index].name()
+    }
+
+    fn find_crossovers(&mut self) {
+        let totals = match &self.totals {
+            Some(totals) => totals,
+            None => return,
+        };
+
+        for a in 0..totals.len() {
+            for b in a + 1..totals.len() {
+                let mut leader = None;
+                for (i, date) in self.dates.iter().enumerate() {
+                    let current = match totals[a][i].cmp(&totals[b][i]) {
+                        std::cmp::Ordering::Greater => Some(a),
+                        std::cmp::Ordering::Less => Some(b),
+                        std::cmp::Ordering::Equal => continue,
+                    };
+                    if leader.is_some() && leader != current {
+                        let winner = current.unwrap();
+                        self.crossovers.push(Crossover {
+                            date: *date,
+                            winner,
+                            loser: if winner == a { b } else { a },
+                        });
+                    }
+                    leader = current;
+                }
+            }
+        }
+        self.crossovers.sort_by_key(|c| c.date);
+    }
+}
+
+impl App {
+    pub(super) fn show_sweep(&mut self, ctx: &egui::Context) {
+        let mut open = self.sweep_open;
+        egui::Window::new(tr!("收益临界点"))
+            .id(egui::Id::new("sweep"))
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("支取日期从"));
+                    TextEdit::singleline(&mut self.sweep_from)
+                        .desired_width(80.0)
+                        .ui(ui);
+                    ui.label(tr!("到"));
+                    TextEdit::singleline(&mut self.sweep_to)
+                        .desired_width(80.0)
+                        .ui(ui);
+                    if ui.button(tr!("计算")).clicked() {
+                        self.start_sweep();
+                    }
+                });
+                if let Some(e) = &self.sweep_error {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+
+                self.poll_sweep();
+
+                let sweep = match &self.sweep {
+                    Some(sweep) => sweep,
+                    None => {
+                        ui.label(tr!("勾选至少两个产品，输入支取日期范围后点击计算"));
+                        return;
+                    }
+                };
+                let totals = match &sweep.totals {
+                    Some(totals) => totals,
+                    None => {
+                        ui.label(tr!("计算中…"));
+                        return;
+                    }
+                };
+
+                ui.separator();
+                ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                    if sweep.crossovers.is_empty() {
+                        ui.label(tr!("所选日期范围内没有收益交叉"));
+                    }
+                    for c in &sweep.crossovers {
+                        ui.label(tr!(
+                            "{} 起 {} 超过 {}",
+                            c.date,
+                            sweep.name(c.winner),
+                            sweep.name(c.loser)
+                        ));
+                    }
+                });
+
+                ui.separator();
+                let save_date = calculator::u32_to_date(sweep.order.save_date).unwrap();
+                Plot::new("收益临界点图")
+                    .legend(Legend::default())
+                    .show(ui, |plot_ui| {
+                        for (i, series) in totals.iter().enumerate() {
+                            let values = sweep.dates.iter().zip(series).map(|(date, total)| {
+                                let date = calculator::u32_to_date(*date).unwrap();
+                                Value::new(
+                                    (date - save_date).whole_days() as f64,
+                                    total.to_f64().unwrap_or_default(),
+                                )
+                            });
+                            plot_ui.line(
+                                Line::new(Values::from_values_iter(values)).name(sweep.name(i)),
+                            );
+                        }
+                    });
+            });
+        self.sweep_open = open;
+    }
+
+    fn start_sweep(&mut self) {
+        let products: Vec<Product> = self
+            .cfg
+            .products
+            .iter()
+            .filter(|p| p.selected)
+            .copied()
+            .collect();
+        self.sweep_error = None;
+        if products.len() < 2 {
+            self.sweep = None;
+            return;
+        }
+
+        let from = self.sweep_from.trim().parse().unwrap_or_default();
+        let to = self.sweep_to.trim().parse().unwrap_or_default();
+        match Sweep::new(&self.cfg.order, products, from, to) {
+            Ok(sweep) => {
+                let reqs = sweep
+                    .products
+                    .iter()
+                    .flat_map(|p| sweep.dates.iter().map(|d| sweep.req(p, *d)))
+                    .collect();
+                self.request(Lane::Sweep, reqs);
+                self.sweep = Some(sweep);
+            }
+            Err(e) => {
+                self.sweep = None;
+                self.sweep_error = Some(e);
+            }
+        }
+    }
+
+    // 结果全部算完后再汇总
+    fn poll_sweep(&mut self) {
+        let sweep = match &mut self.sweep {
+            Some(sweep) if sweep.totals.is_none() => sweep,
+            _ => return,
+        };
+
+        let mut totals = Vec::with_capacity(sweep.products.len());
+        for product in &sweep.products {
+            let mut series = Vec::with_capacity(sweep.dates.len());
+            for date in &sweep.dates {
+                match self.batches.get(Lane::Sweep, &sweep.req(product, *date)) {
+                    Some((interest, bean_int)) => {
+                        series.push(self.cfg.bean_value.total(interest, bean_int))
+                    }
+                    None => return,
+                }
+            }
+            totals.push(series);
+        }
+
+        sweep.totals = Some(totals);
+        sweep.find_crossovers();
+    }
+}
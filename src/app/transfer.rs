@@ -0,0 +1,166 @@
+use anyhow::anyhow;
+use eframe::egui;
+use eframe::egui::{Color32, RichText};
+use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy::ToZero;
+
+use crate::app::calculator;
+use crate::app::history::Field;
+use crate::app::i18n::tr;
+use crate::app::App;
+
+impl App {
+    pub(super) fn show_transfer(&mut self, ctx: &egui::Context) {
+        let mut open = self.transfer_open;
+        egui::Window::new(tr!("大额存单转让"))
+            .id(egui::Id::new("transfer"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let cd = self.cfg.cd;
+                egui::Grid::new("大额存单").show(ui, |ui| {
+                    ui.label(tr!("面额"));
+                    let mut face = format!("{:.2}", cd.face);
+                    if ui.text_edit_singleline(&mut face).changed() {
+                        self.cd_face_changed(&*face);
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("票面利率(%)"));
+                    let mut rate = format!("{:.2}", cd.rate);
+                    if ui.text_edit_singleline(&mut rate).changed() {
+                        self.cd_rate_changed(&*rate);
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("起息日"));
+                    let mut issue_date = cd.issue_date.to_string();
+                    if ui.text_edit_singleline(&mut issue_date).changed() {
+                        issue_date.truncate(8);
+                        self.cd_issue_date_changed(&*issue_date);
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("到期日"));
+                    let mut maturity = cd.maturity.to_string();
+                    if ui.text_edit_singleline(&mut maturity).changed() {
+                        maturity.truncate(8);
+                        self.cd_maturity_changed(&*maturity);
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("转让日期"));
+                    let mut transfer_date = cd.transfer_date.to_string();
+                    if ui.text_edit_singleline(&mut transfer_date).changed() {
+                        transfer_date.truncate(8);
+                        self.cd_transfer_date_changed(&*transfer_date);
+                    }
+                    ui.end_row();
+
+                    ui.label(tr!("买方收益率(%)"));
+                    let mut buyer_yield = format!("{:.2}", cd.buyer_yield);
+                    if ui.text_edit_singleline(&mut buyer_yield).changed() {
+                        self.cd_buyer_yield_changed(&*buyer_yield);
+                    }
+                    ui.end_row();
+                });
+                if let Some(e) = &self.transfer_error {
+                    ui.colored_label(Color32::RED, e.to_string());
+                }
+
+                ui.separator();
+                match calculator::value_cd(&self.cfg.cd) {
+                    Ok(value) => {
+                        egui::Grid::new("转让估值").show(ui, |ui| {
+                            ui.label(tr!("到期本息"));
+                            ui.label(format!("{:.2}", value.maturity_value));
+                            ui.end_row();
+
+                            ui.label(tr!("已计利息"));
+                            ui.label(tr!(
+                                "{}（持有{}天）",
+                                format!("{:.2}", value.accrued),
+                                value.held_days
+                            ));
+                            ui.end_row();
+
+                            ui.label(tr!("转让价"));
+                            ui.label(RichText::new(format!("{:.2}", value.price)).strong());
+                            ui.end_row();
+
+                            ui.label(tr!("卖方年化收益率"));
+                            ui.label(format!("{:.4}%", value.seller_yield));
+                            ui.end_row();
+
+                            ui.label(tr!("剩余天数"));
+                            ui.label(value.left_days.to_string());
+                            ui.end_row();
+                        });
+                    }
+                    Err(e) => {
+                        ui.label(RichText::from(e.to_string()).color(Color32::RED));
+                    }
+                }
+            });
+        self.transfer_open = open;
+    }
+
+    fn cd_face_changed(&mut self, face: &str) {
+        if let Ok(mut v) = face.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v >= Decimal::new(1000_0000_0000, 0) {
+                self.transfer_error = Some(anyhow!(tr!("一千亿啊，土豪，还需要算吗？")))
+            } else {
+                self.transfer_error = None;
+                self.edit(Field::Cd);
+                self.cfg.cd.face = v;
+            }
+        }
+    }
+
+    fn cd_rate_changed(&mut self, rate: &str) {
+        if let Ok(mut v) = rate.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v > Decimal::TEN {
+                self.transfer_error = Some(anyhow!(tr!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！")));
+            } else {
+                self.transfer_error = None;
+                self.edit(Field::Cd);
+                self.cfg.cd.rate = v;
+            }
+        }
+    }
+
+    fn cd_issue_date_changed(&mut self, issue_date: &str) {
+        if let Ok(v) = issue_date.parse() {
+            self.edit(Field::Cd);
+            self.cfg.cd.issue_date = v;
+        }
+    }
+
+    fn cd_maturity_changed(&mut self, maturity: &str) {
+        if let Ok(v) = maturity.parse() {
+            self.edit(Field::Cd);
+            self.cfg.cd.maturity = v;
+        }
+    }
+
+    fn cd_transfer_date_changed(&mut self, transfer_date: &str) {
+        if let Ok(v) = transfer_date.parse() {
+            self.edit(Field::Cd);
+            self.cfg.cd.transfer_date = v;
+        }
+    }
+
+    fn cd_buyer_yield_changed(&mut self, buyer_yield: &str) {
+        if let Ok(mut v) = buyer_yield.parse::<Decimal>() {
+            v = v.round_dp_with_strategy(2, ToZero);
+            if v > Decimal::TEN {
+                self.transfer_error = Some(anyhow!(tr!("哪里有这么高的利率，苟富贵勿相忘啊，兄弟！")));
+            } else {
+                self.transfer_error = None;
+                self.edit(Field::Cd);
+                self.cfg.cd.buyer_yield = v;
+            }
+        }
+    }
+}
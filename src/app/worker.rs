@@ -0,0 +1,238 @@
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_channel::{Receiver, Sender};
+use eframe::egui;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::executor::ThreadPoolBuilder;
+use rayon::prelude::*;
+use rust_decimal::Decimal;
+
+use crate::app::calculator::{self, Req};
+
+// 每次最多计算的数量，算完一批再检查是否有更高优先级或已过期
+const CHUNK: usize = 256;
+
+// 计算请求的来源，靠前的优先计算；同一来源的新请求会取消旧请求
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Lane {
+    Visible,
+    Compare,
+    Sweep,
+    Sensitivity,
+}
+
+const LANES: usize = 4;
+
+struct Job {
+    lane: Lane,
+    generation: u64,
+    reqs: Vec<Req>,
+}
+
+type Results = HashMap<Req, (Decimal, Decimal)>;
+
+#[derive(Default)]
+struct State {
+    generations: [AtomicU64; LANES],
+    total: AtomicUsize,
+    done: AtomicUsize,
+}
+
+impl State {
+    fn is_stale(&self, job: &Job) -> bool {
+        self.generations[job.lane as usize].load(Ordering::Acquire) != job.generation
+    }
+}
+
+pub struct Worker {
+    job_s: Sender<Job>,
+    res_r: Receiver<(Lane, Results)>,
+    state: Arc<State>,
+    pending: HashMap<Req, Lane>,
+}
+
+impl Worker {
+    pub fn new(egui_ctx: egui::Context) -> Self {
+        //job 需要计算的key (本金-购买日期-支取日期-产品存期-存期类型-利率-邦豆利率-滚存类型)
+        let (job_s, job_r) = async_channel::unbounded::<Job>();
+        //res 计算结果HashMap<key,value> ()
+        let (res_s, res_r) = async_channel::unbounded::<(Lane, Results)>();
+        let state = Arc::new(State::default());
+
+        let task = run(job_r, res_s, state.clone(), egui_ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        ThreadPoolBuilder::new()
+            .pool_size(1)
+            .create()
+            .unwrap()
+            .spawn_ok(task);
+        // 浏览器中没有线程，在页面的事件循环里计算
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(task);
+
+        Self {
+            job_s,
+            res_r,
+            state,
+            pending: HashMap::new(),
+        }
+    }
+
+    // 提交一批未缓存的请求，取消同一来源尚未完成的旧请求
+    pub fn submit(&mut self, lane: Lane, reqs: Vec<Req>) {
+        if reqs
+            .iter()
+            .all(|req| self.pending.get(req) == Some(&lane))
+            && self.pending.values().filter(|l| **l == lane).count() == reqs.len()
+        {
+            return;
+        }
+
+        self.pending.retain(|_, l| *l != lane);
+        let generation = self.state.generations[lane as usize].fetch_add(1, Ordering::AcqRel) + 1;
+        if reqs.is_empty() {
+            return;
+        }
+
+        reqs.iter().for_each(|req| {
+            self.pending.insert(*req, lane);
+        });
+        self.state.total.fetch_add(reqs.len(), Ordering::AcqRel);
+        // 无界通道，发送不会阻塞
+        self.job_s
+            .try_send(Job {
+                lane,
+                generation,
+                reqs,
+            })
+            .unwrap();
+    }
+
+    pub fn cancel(&mut self, lane: Lane) {
+        self.submit(lane, vec![]);
+    }
+
+    pub fn try_recv(&mut self) -> Option<(Lane, Results)> {
+        let (lane, res) = self.res_r.try_recv().ok()?;
+        res.keys().for_each(|req| {
+            self.pending.remove(req);
+        });
+        Some((lane, res))
+    }
+
+    // (已完成, 总数)，空闲时返回 None
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        let total = self.state.total.load(Ordering::Acquire);
+        let done = self.state.done.load(Ordering::Acquire);
+        if done >= total {
+            // 只有界面线程提交任务，此时工作线程空闲，可以安全清零
+            self.state.total.store(0, Ordering::Release);
+            self.state.done.store(0, Ordering::Release);
+            return None;
+        }
+        Some((done, total))
+    }
+}
+
+// 每个来源最近一次请求的全部结果，保留到该来源下次请求，不受缓存淘汰影响
+#[derive(Default)]
+pub struct Batches {
+    lanes: HashMap<Lane, HashMap<Req, Option<(Decimal, Decimal)>>>,
+}
+
+impl Batches {
+    pub fn get(&self, lane: Lane, req: &Req) -> Option<(Decimal, Decimal)> {
+        self.lanes.get(&lane)?.get(req).copied().flatten()
+    }
+
+    // 与上次请求的集合相同
+    pub fn is_same(&self, lane: Lane, reqs: &HashSet<Req>) -> bool {
+        match self.lanes.get(&lane) {
+            Some(batch) => batch.len() == reqs.len() && reqs.iter().all(|r| batch.contains_key(r)),
+            None => false,
+        }
+    }
+
+    pub fn set(&mut self, lane: Lane, batch: HashMap<Req, Option<(Decimal, Decimal)>>) {
+        self.lanes.insert(lane, batch);
+    }
+
+    pub fn clear(&mut self, lane: Lane) {
+        self.lanes.remove(&lane);
+    }
+
+    // 只接收仍在该来源当前请求中的结果
+    pub fn deliver(&mut self, lane: Lane, res: &Results) {
+        if let Some(batch) = self.lanes.get_mut(&lane) {
+            res.iter().for_each(|(req, value)| {
+                if let Some(slot) = batch.get_mut(req) {
+                    *slot = Some(*value);
+                }
+            });
+        }
+    }
+}
+
+// 常驻的计算循环，按来源优先级分批计算
+async fn run(
+    job_r: Receiver<Job>,
+    res_s: Sender<(Lane, Results)>,
+    state: Arc<State>,
+    egui_ctx: egui::Context,
+) {
+    let mut queue: Vec<Job> = vec![];
+
+    loop {
+        if queue.is_empty() {
+            match job_r.recv().await {
+                Ok(job) => queue.push(job),
+                Err(_) => break,
+            }
+        }
+        while let Ok(job) = job_r.try_recv() {
+            queue.push(job);
+        }
+
+        // 丢弃过期的批次，计入进度
+        queue.retain(|job| {
+            let stale = state.is_stale(job);
+            if stale {
+                state.done.fetch_add(job.reqs.len(), Ordering::AcqRel);
+            }
+            !stale
+        });
+
+        let index = match queue
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, job)| job.lane)
+            .map(|(i, _)| i)
+        {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let job = &mut queue[index];
+        let lane = job.lane;
+        let chunk: Vec<Req> = job.reqs.drain(..min(CHUNK, job.reqs.len())).collect();
+        if job.reqs.is_empty() {
+            queue.remove(index);
+        }
+
+        let res: Results = chunk
+            .into_par_iter()
+            .map(|req| (req, calculator::calc(&req)))
+            .collect();
+        state.done.fetch_add(res.len(), Ordering::AcqRel);
+
+        res_s.send((lane, res)).await.ok();
+        egui_ctx.request_repaint();
+
+        // 每算完一批让出事件循环，页面才能响应输入和重绘
+        #[cfg(target_arch = "wasm32")]
+        gloo_timers::future::TimeoutFuture::new(0).await;
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::Config;
+use crate::app::i18n::tr;
+
+// 工作表名最长31个字符
+const SHEET_NAME_MAX: usize = 31;
+
+// 第一张表为产品对比，之后每个产品一张续存明细表，金额写为保留两位小数的数值
+pub fn export(cfg: &Config, path: &Path) -> Result<()> {
+    let mut order = cfg.order;
+    calculator::check_date(&mut order)?;
+    calculator::check_withdrawal(&order)?;
+
+    let err = |e: XlsxError| anyhow!(tr!("导出Excel失败！{}", e));
+    let bold = Format::new().set_bold();
+    let money = Format::new().set_num_format("0.00");
+
+    let mut workbook = Workbook::new();
+    let value = cfg.bean_value;
+    let results: Vec<_> = cfg
+        .products
+        .iter()
+        .map(|p| {
+            let req = Req::new(&order, p);
+            (p, req, calculator::calc(&req))
+        })
+        .collect();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(tr!("产品对比")).map_err(err)?;
+    header(
+        sheet,
+        &[
+            tr!("产品"),
+            tr!("利率(%)"),
+            tr!("邦豆利率(%)"),
+            tr!("续存类型"),
+            tr!("利息"),
+            tr!("邦豆利息"),
+            tr!("总收益"),
+        ],
+        &bold,
+    )
+    .map_err(err)?;
+    for (i, (product, _, (interest, bean_int))) in results.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, product.name()).map_err(err)?;
+        number(sheet, row, 1, product.int_rate, &money).map_err(err)?;
+        number(sheet, row, 2, product.bean_rate, &money).map_err(err)?;
+        sheet
+            .write_string(row, 3, product.renew_type.to_string())
+            .map_err(err)?;
+        number(sheet, row, 4, *interest, &money).map_err(err)?;
+        number(sheet, row, 5, *bean_int, &money).map_err(err)?;
+        number(sheet, row, 6, value.total(*interest, *bean_int), &money).map_err(err)?;
+    }
+    sheet.set_column_width(0, 20).map_err(err)?;
+
+    let mut names = HashSet::from([tr!("产品对比").to_owned()]);
+    for (i, (product, req, _)) in results.iter().enumerate() {
+        let sheet = workbook.add_worksheet();
+        sheet
+            .set_name(sheet_name(&mut names, i, &product.name()))
+            .map_err(err)?;
+        header(
+            sheet,
+            &[
+                tr!("期次"),
+                tr!("起始日"),
+                tr!("结束日"),
+                tr!("本金"),
+                tr!("累计收益"),
+                tr!("累计邦豆利息"),
+                tr!("状态"),
+            ],
+            &bold,
+        )
+        .map_err(err)?;
+        for (j, period) in calculator::schedule(req).iter().enumerate() {
+            let row = j as u32 + 1;
+            sheet.write_number(row, 0, (j + 1) as f64).map_err(err)?;
+            sheet
+                .write_string(row, 1, calculator::date_to_u32(period.start).to_string())
+                .map_err(err)?;
+            sheet
+                .write_string(row, 2, calculator::date_to_u32(period.end).to_string())
+                .map_err(err)?;
+            number(sheet, row, 3, period.principal, &money).map_err(err)?;
+            number(sheet, row, 4, period.interest, &money).map_err(err)?;
+            number(sheet, row, 5, period.bean_int, &money).map_err(err)?;
+            let status = if period.matured { tr!("到期") } else { tr!("未到期") };
+            sheet.write_string(row, 6, status).map_err(err)?;
+        }
+    }
+
+    workbook.save(path).map_err(err)
+}
+
+fn header(sheet: &mut Worksheet, titles: &[&str], bold: &Format) -> Result<(), XlsxError> {
+    for (col, title) in titles.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, bold)?;
+        sheet.set_column_width(col as u16, 14)?;
+    }
+    Ok(())
+}
+
+fn number(
+    sheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    value: Decimal,
+    format: &Format,
+) -> Result<(), XlsxError> {
+    sheet.write_number_with_format(row, col, value.to_f64().unwrap_or_default(), format)?;
+    Ok(())
+}
+
+// 工作表名不能重复，也不能含 []:*?/\ ，同名产品加序号区分
+fn sheet_name(names: &mut HashSet<String>, index: usize, name: &str) -> String {
+    let clean: String = name
+        .chars()
+        .filter(|c| !"[]:*?/\\".contains(*c))
+        .take(SHEET_NAME_MAX)
+        .collect();
+    let name = if names.contains(&clean) || clean.is_empty() {
+        let prefix = format!("{}.", index + 1);
+        let rest: String = clean
+            .chars()
+            .take(SHEET_NAME_MAX - prefix.chars().count())
+            .collect();
+        format!("{prefix}{rest}")
+    } else {
+        clean
+    };
+    names.insert(name.clone());
+    name
+}
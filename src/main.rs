@@ -1,12 +1,86 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 // hide console window on Windows in release
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+use app::i18n::{self, tr, Lang};
+
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+mod server;
 
+// 发布版没有控制台窗口，命令行子命令需要输出到启动它的控制台
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    // deposit-calculator serve [端口]：只启动本机 HTTP/JSON 接口，不打开界面
+    // deposit-calculator report <方案文件> <报告文件.html|.pdf>：按方案生成报告
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        #[cfg(windows)]
+        attach_console();
+        i18n::set_lang(Lang::from_env());
+    }
+    if args.get(1).map(String::as_str) == Some("report") {
+        let (input, output) = match (args.get(2), args.get(3)) {
+            (Some(input), Some(output)) => (Path::new(input), Path::new(output)),
+            _ => {
+                eprintln!(
+                    "{}",
+                    tr!("用法：deposit-calculator report <方案文件> <报告文件.html|.pdf>")
+                );
+                std::process::exit(2);
+            }
+        };
+        let res = app::scenario::load(input).and_then(|cfg| app::report::export(&cfg, output));
+        if let Err(e) = res {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("serve") {
+        let port = match args.get(2).map(|p| p.parse()) {
+            Some(Ok(port)) => port,
+            Some(Err(_)) => {
+                eprintln!("{}", tr!("端口有误！"));
+                std::process::exit(2);
+            }
+            None => server::DEFAULT_PORT,
+        };
+        if let Err(e) = server::serve(port) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     eframe::run_native(
         "存款计算器",
         eframe::NativeOptions::default(),
         Box::new(|cc| Box::new(app::App::new(cc))),
     );
 }
+
+// 网页版用 trunk 构建，画在 index.html 中的 canvas 上，配置保存在浏览器的 localStorage
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    eframe::start_web(
+        "deposit_calculator_canvas",
+        Box::new(|cc| Box::new(app::App::new(cc))),
+    )
+    .expect("启动失败");
+}
@@ -0,0 +1,268 @@
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Result};
+use rayon::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::app::calculator::{self, Req};
+use crate::app::config::{
+    BeanRule, FixedTerm, Notice, Order, OrderMode, Payout, Product, ProductKind, Recurring,
+    RenewType, TermType, Withdrawal,
+};
+use crate::app::i18n::tr;
+
+pub const DEFAULT_PORT: u16 = 8765;
+
+// 请求体上限，防止误传大文件
+const BODY_MAX: u64 = 16 * 1024 * 1024;
+
+// 接口的请求格式，与内部的 Req 分开定义，计算规则变化时不影响调用方；
+// 字段都有默认值，零存整取时给出 recurring
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct CalcReq {
+    principal: Decimal,
+    save_date: u32,
+    draw_date: u32,
+    recurring: Option<Recurring>,
+    withdrawal: Option<Withdrawal>,
+    kind: ProductKind,
+    term: u16,
+    term_type: TermType,
+    int_rate: Decimal,
+    bean_rate: Decimal,
+    renew_type: RenewType,
+    fixed: FixedTerm,
+    notice: Notice,
+    bean_rule: BeanRule,
+    payout: Payout,
+}
+
+impl CalcReq {
+    fn order(&self) -> Order {
+        Order {
+            mode: match self.recurring {
+                Some(_) => OrderMode::Recurring,
+                None => OrderMode::LumpSum,
+            },
+            principal: self.principal,
+            recurring: self.recurring.unwrap_or_default(),
+            save_date: self.save_date,
+            draw_date: self.draw_date,
+            days: 0,
+            withdrawal: self.withdrawal,
+        }
+    }
+
+    // 与界面相同的日期校验，避免非法日期导致计算出错
+    fn to_req(&self) -> Result<Req> {
+        let mut order = self.order();
+        calculator::check_date(&mut order)?;
+        calculator::check_withdrawal(&order)?;
+        let product = Product {
+            kind: self.kind,
+            term: self.term,
+            term_type: self.term_type,
+            fixed: self.fixed,
+            notice: self.notice,
+            int_rate: self.int_rate,
+            bean_rate: self.bean_rate,
+            bean_rule: self.bean_rule,
+            renew_type: self.renew_type,
+            payout: self.payout,
+            ..Default::default()
+        };
+        Ok(Req::new(&order, &product))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum CalcResult {
+    Ok {
+        interest: Decimal,
+        bean_int: Decimal,
+    },
+    Err {
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order: Option<Order>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PeriodJson {
+    start: u32,
+    end: u32,
+    principal: Decimal,
+    interest: Decimal,
+    bean_int: Decimal,
+    matured: bool,
+}
+
+#[derive(Serialize)]
+struct PayoutJson {
+    date: u32,
+    amount: Decimal,
+    demand_int: Decimal,
+}
+
+#[derive(Serialize)]
+struct ScheduleResult {
+    interest: Decimal,
+    bean_int: Decimal,
+    periods: Vec<PeriodJson>,
+    payouts: Vec<PayoutJson>,
+}
+
+#[derive(Serialize)]
+struct ErrorJson {
+    error: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Batch {
+    Many(Vec<CalcReq>),
+    One(CalcReq),
+}
+
+// 允许网页中的内部工具跨域调用
+const HEADERS: [(&str, &str); 4] = [
+    ("Content-Type", "application/json; charset=utf-8"),
+    ("Access-Control-Allow-Origin", "*"),
+    ("Access-Control-Allow-Methods", "POST, OPTIONS"),
+    ("Access-Control-Allow-Headers", "Content-Type"),
+];
+
+// 只监听本机，接口：
+// POST /calc      一个或一组 CalcReq，返回每个的利息与邦豆利息
+// POST /check     一个 CalcReq，只校验其中的日期并补全天数
+// POST /schedule  一个 CalcReq，返回每期明细与付息明细
+pub fn serve(port: u16) -> Result<()> {
+    let server =
+        Server::http(("127.0.0.1", port)).map_err(|e| anyhow!(tr!("监听端口失败！{}", e)))?;
+    println!("{}", tr!("存款计算器接口已启动：http://127.0.0.1:{}", port));
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = if *request.method() == Method::Options {
+            // 跨域预检
+            (204, String::new())
+        } else {
+            match handle(&mut request) {
+                Ok(body) => (200, body),
+                Err(e) => (
+                    400,
+                    serde_json::to_string(&ErrorJson {
+                        error: e.to_string(),
+                    })?,
+                ),
+            }
+        };
+        let mut response = Response::from_string(body).with_status_code(status);
+        for (name, value) in HEADERS {
+            let header = Header::from_bytes(name, value).map_err(|_| anyhow!(tr!("响应头有误！")))?;
+            response.add_header(header);
+        }
+        if let Err(e) = request.respond(response) {
+            eprintln!("{}", tr!("响应失败！{}", e));
+        }
+    }
+    Ok(())
+}
+
+fn handle(request: &mut Request) -> Result<String> {
+    if *request.method() != Method::Post {
+        bail!(tr!("只支持POST请求！"))
+    }
+
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(BODY_MAX)
+        .read_to_string(&mut body)
+        .map_err(|e| anyhow!(tr!("读取请求失败！{}", e)))?;
+    let parse_err = |e: serde_json::Error| anyhow!(tr!("请求格式有误！{}", e));
+
+    let json = match request.url() {
+        "/calc" => {
+            let reqs = match serde_json::from_str(&body).map_err(parse_err)? {
+                Batch::Many(reqs) => reqs,
+                Batch::One(req) => vec![req],
+            };
+            let results: Vec<CalcResult> = reqs
+                .par_iter()
+                .map(|req| match req.to_req() {
+                    Ok(req) => {
+                        let (interest, bean_int) = calculator::calc(&req);
+                        CalcResult::Ok { interest, bean_int }
+                    }
+                    Err(e) => CalcResult::Err {
+                        error: e.to_string(),
+                    },
+                })
+                .collect();
+            serde_json::to_string(&results)?
+        }
+        "/check" => {
+            let req: CalcReq = serde_json::from_str(&body).map_err(parse_err)?;
+            let mut order = req.order();
+            let checked = calculator::check_date(&mut order)
+                .and_then(|_| calculator::check_withdrawal(&order));
+            let result = match checked {
+                Ok(()) => CheckResult {
+                    ok: true,
+                    order: Some(order),
+                    error: None,
+                },
+                Err(e) => CheckResult {
+                    ok: false,
+                    order: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            serde_json::to_string(&result)?
+        }
+        "/schedule" => {
+            let req: CalcReq = serde_json::from_str(&body).map_err(parse_err)?;
+            let req = req.to_req()?;
+            let periods = calculator::schedule(&req);
+            let payouts = calculator::payouts(&req, &periods);
+            let (interest, bean_int) = calculator::calc(&req);
+            serde_json::to_string(&ScheduleResult {
+                interest,
+                bean_int,
+                periods: periods
+                    .iter()
+                    .map(|p| PeriodJson {
+                        start: calculator::date_to_u32(p.start),
+                        end: calculator::date_to_u32(p.end),
+                        principal: p.principal,
+                        interest: p.interest,
+                        bean_int: p.bean_int,
+                        matured: p.matured,
+                    })
+                    .collect(),
+                payouts: payouts
+                    .iter()
+                    .map(|p| PayoutJson {
+                        date: calculator::date_to_u32(p.date),
+                        amount: p.amount,
+                        demand_int: p.demand_int,
+                    })
+                    .collect(),
+            })?
+        }
+        url => bail!(tr!("没有这个接口：{}", url)),
+    };
+    Ok(json)
+}